@@ -0,0 +1,30 @@
+//! Compares the sequential and the unrolled expansion of range loops.
+//!
+//! Run with `cargo bench --bench unordered_sum`.
+
+#[macro_use]
+extern crate lup;
+
+use lup::{Sum, SumUnordered};
+use std::hint::black_box;
+use std::time::Instant;
+
+fn bench<T: std::fmt::Debug>(name: &str, f: impl Fn() -> T) {
+    let runs = 20;
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..runs {black_box(f());}
+    println!("{:<24} {:>10.3} ms", name, start.elapsed().as_secs_f64() * 1000.0 / runs as f64);
+}
+
+fn main() {
+    let n = 10_000_000;
+    let xs: Vec<f64> = (0..n).map(|i| (i as f64 * 0.37).sin()).collect();
+    let ns: Vec<u64> = (0..n as u64).collect();
+
+    bench("Sum<f64>", || lup!(Sum<f64>: i by xs => {xs[i]}));
+    bench("SumUnordered<f64>", || lup!(SumUnordered<f64>: i by xs => {xs[i]}));
+    bench("Iterator::sum f64", || xs.iter().sum::<f64>());
+    bench("Sum<u64>", || lup!(Sum<u64>: i by ns => {ns[i]}));
+    bench("Iterator::sum u64", || ns.iter().sum::<u64>());
+}
@@ -3,6 +3,7 @@ extern crate lup;
 
 use lup::{Any, Max};
 
+#[allow(clippy::useless_vec)]
 fn main() {
     let data = vec![
         vec![1, 2, 6, 4, 5, 3],
@@ -3,6 +3,7 @@ extern crate lup;
 
 use lup::Max;
 
+#[allow(clippy::useless_vec)]
 fn main() {
     let data = vec![
         (1, 1),
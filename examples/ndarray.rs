@@ -0,0 +1,22 @@
+#[macro_use]
+extern crate lup;
+extern crate ndarray;
+
+use lup::{Any, Max};
+use ndarray::Array2;
+
+fn main() {
+    let a = Array2::from_shape_vec((2, 3), vec![
+        1.0, 5.0, 2.0,
+        4.0, 3.0, 6.0,
+    ]).unwrap();
+
+    // Find the maximum value.
+    let max = lup!(Max<_, f64>: i, j by2 a => {a[[i, j]]});
+    println!("{}", max.value); // Prints `6`.
+    println!("{:?}", max.evidence); // Prints `Some((1, 2))`.
+
+    // Look for a number greater than 4.
+    let b = lup!(Any<_>: i, j by2 a => {a[[i, j]] > 4.0});
+    println!("{:?}", b.evidence); // Prints `Some((0, 1))`.
+}
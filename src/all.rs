@@ -1,66 +1,143 @@
 use *;
 
 /// For all loop.
-pub struct All<I>(pub Option<I>);
+///
+/// The value is `true` if all items in the loop satisfy the condition.
+/// Otherwise, the evidence is the index of the first counterexample.
+///
+/// When nesting loops, an inner loop might report `false` without evidence,
+/// e.g. a custom loop or a hand-built secret.
+/// The outer loop then reports `false` without evidence.
+#[derive(Debug, Clone)]
+pub struct All<I>(pub Secret<I, bool>);
+
+impl<I> Default for All<I> {
+    fn default() -> All<I> {All(Secret {evidence: None, value: true})}
+}
+
+impl<K> Lup<K, bool> for All<K> {
+    type Inner = Secret<K, bool>;
+    fn start() -> All<K> {All::default()}
+    fn it(&mut self, ind: K, val: bool) -> bool {
+        if !val {
+            self.0 = Secret {evidence: Some(ind), value: false};
+            false
+        } else {
+            true
+        }
+    }
+    fn unwrap(self) -> Secret<K, bool> {self.0}
+}
 
 macro_rules! all_impl {
     ($usize:ty) => {
-        impl Lup<$usize, bool> for All<$usize> {
-            type Inner = Secret<$usize, bool>;
-            fn start() -> All<$usize> {All(None)}
-            fn it(&mut self, ind: $usize, val: bool) -> bool {
-                if !val {
-                    self.0 = Some(ind);
-                    false
-                } else {
+        impl Lup<$usize, Secret<$usize, bool>> for All<($usize, $usize)> {
+            type Inner = Secret<($usize, $usize), bool>;
+            fn start() -> All<($usize, $usize)> {All::default()}
+            fn it(&mut self, ind: $usize, val: Secret<$usize, bool>) -> bool {
+                if val.value {
                     true
+                } else {
+                    self.0 = Secret {evidence: val.evidence.map(|a| (ind, a)), value: false};
+                    false
                 }
             }
-            fn unwrap(self) -> Secret<$usize, bool> {
-                Secret {value: self.0.is_none(), evidence: self.0}
-            }
+            fn unwrap(self) -> Secret<($usize, $usize), bool> {self.0}
         }
 
-        impl Lup<$usize, Secret<$usize, bool>> for All<($usize, $usize)> {
-            type Inner = Secret<($usize, $usize), bool>;
-            fn start() -> All<($usize, $usize)> {All(None)}
-            fn it(&mut self, ind: $usize, val: Secret<$usize, bool>) -> bool {
+        impl<K: Key> Lup<$usize, Secret<K, bool>> for All<($usize, K)> {
+            type Inner = Secret<($usize, K), bool>;
+            fn start() -> All<($usize, K)> {All::default()}
+            fn it(&mut self, ind: $usize, val: Secret<K, bool>) -> bool {
                 if val.value {
                     true
                 } else {
-                    if let Some(ind2) = val.evidence {
-                        self.0 = Some((ind, ind2));
-                        false
-                    } else {
-                        true
-                    }
+                    self.0 = Secret {evidence: val.evidence.map(|key| (ind, key)), value: false};
+                    false
                 }
             }
-            fn unwrap(self) -> Secret<($usize, $usize), bool> {
-                Secret {value: self.0.is_none(), evidence: self.0}
-            }
+            fn unwrap(self) -> Secret<($usize, K), bool> {self.0}
         }
 
         impl Lup<$usize, Secret<($usize, $usize), bool>> for All<($usize, $usize, $usize)> {
             type Inner = Secret<($usize, $usize, $usize), bool>;
-            fn start() -> All<($usize, $usize, $usize)> {All(None)}
+            fn start() -> All<($usize, $usize, $usize)> {All::default()}
             fn it(&mut self, ind: $usize, val: Secret<($usize, $usize), bool>) -> bool {
                 if val.value {
                     true
                 } else {
-                    if let Some((a, b)) = val.evidence {
-                        self.0 = Some((ind, a, b));
-                        false
-                    } else {
-                        true
-                    }
+                    self.0 = Secret {evidence: val.evidence.map(|(a, b)| (ind, a, b)), value: false};
+                    false
                 }
             }
-            fn unwrap(self) -> Secret<($usize, $usize, $usize), bool> {
-                Secret {value: self.0.is_none(), evidence: self.0}
+            fn unwrap(self) -> Secret<($usize, $usize, $usize), bool> {self.0}
+        }
+
+        impl Lup<$usize, Secret<($usize, $usize, $usize), bool>>
+        for All<($usize, $usize, $usize, $usize)> {
+            type Inner = Secret<($usize, $usize, $usize, $usize), bool>;
+            fn start() -> All<($usize, $usize, $usize, $usize)> {All::default()}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize, $usize), bool>) -> bool {
+                if val.value {
+                    true
+                } else {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b, c)| (ind, a, b, c)),
+                        value: false
+                    };
+                    false
+                }
             }
+            fn unwrap(self) -> Secret<($usize, $usize, $usize, $usize), bool> {self.0}
         }
     }
 }
 
 all_impl!{usize}
+
+all_impl!{u32}
+
+all_impl!{u16}
+
+/// For all loop over unordered pairs of indices.
+///
+/// Used with the `(i, j) by <list>` syntax, which visits each pair once with `i < j`.
+/// The evidence is the first pair that fails.
+pub type AllPairs = All<(usize, usize)>;
+
+/// For all loop that also counts the checked items.
+///
+/// Unlike `All`, the result tells whether the loop was vacuously true,
+/// i.e. there were no items to check.
+#[derive(Debug, Clone)]
+pub struct AllChecked<I>(pub All<I>, pub usize);
+
+impl<I> Default for AllChecked<I> {
+    fn default() -> AllChecked<I> {AllChecked(All::default(), 0)}
+}
+
+/// The result of an `AllChecked` loop.
+#[derive(Debug, Clone)]
+pub struct Checked<E> {
+    /// The result of the `All` loop.
+    pub secret: Secret<E, bool>,
+    /// The number of checked items.
+    pub iterations: usize,
+}
+
+impl<E> Checked<E> {
+    /// Returns `true` if there were no items to check.
+    pub fn was_vacuous(&self) -> bool {self.iterations == 0}
+}
+
+impl<K> Lup<K, bool> for AllChecked<K> {
+    type Inner = Checked<K>;
+    fn start() -> AllChecked<K> {AllChecked::default()}
+    fn it(&mut self, ind: K, val: bool) -> bool {
+        self.1 += 1;
+        self.0.it(ind, val)
+    }
+    fn unwrap(self) -> Checked<K> {
+        Checked {secret: self.0.unwrap(), iterations: self.1}
+    }
+}
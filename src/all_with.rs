@@ -0,0 +1,101 @@
+use *;
+
+/// For all loop that captures the counterexample.
+///
+/// The body returns `(bool, T)`, where `T` is a payload describing the item,
+/// e.g. a residual computed in the body.
+/// The loop stops at the first `false` and keeps both its index and its payload.
+///
+/// The value of the resulting secret is `None` if the condition holds for all items,
+/// or `Some(payload)` of the counterexample.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::AllWith;
+///
+/// fn main() {
+///     let xs = [1.0, 2.0, 3.0];
+///     let res = lup!(AllWith<_, _>: i by xs => {
+///         let r = xs[i] * xs[i] - 2.0 * xs[i];
+///         (r < 2.0, r)
+///     });
+///     println!("{:?}", res.evidence); // Prints `Some(2)`.
+///     println!("{:?}", res.value); // Prints `Some(3.0)`.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AllWith<I, T>(pub Secret<I, Option<T>>);
+
+impl<K, T> Lup<K, (bool, T)> for AllWith<K, T> {
+    type Inner = Secret<K, Option<T>>;
+    fn start() -> Self {AllWith(Secret {evidence: None, value: None})}
+    fn it(&mut self, ind: K, (ok, payload): (bool, T)) -> bool {
+        if !ok {
+            self.0 = Secret {evidence: Some(ind), value: Some(payload)};
+        }
+        ok
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+macro_rules! all_with_impl {
+    ($usize:ty) => {
+        impl<T> Lup<$usize, Secret<$usize, Option<T>>> for AllWith<($usize, $usize), T> {
+            type Inner = Secret<($usize, $usize), Option<T>>;
+            fn start() -> Self {AllWith(Secret {evidence: None, value: None})}
+            fn it(&mut self, ind: $usize, val: Secret<$usize, Option<T>>) -> bool {
+                if val.value.is_some() {
+                    self.0 = Secret {evidence: val.evidence.map(|a| (ind, a)), value: val.value};
+                    false
+                } else {
+                    true
+                }
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+
+        impl<T> Lup<$usize, Secret<($usize, $usize), Option<T>>>
+        for AllWith<($usize, $usize, $usize), T> {
+            type Inner = Secret<($usize, $usize, $usize), Option<T>>;
+            fn start() -> Self {AllWith(Secret {evidence: None, value: None})}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize), Option<T>>) -> bool {
+                if val.value.is_some() {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b)| (ind, a, b)),
+                        value: val.value
+                    };
+                    false
+                } else {
+                    true
+                }
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+
+        impl<T> Lup<$usize, Secret<($usize, $usize, $usize), Option<T>>>
+        for AllWith<($usize, $usize, $usize, $usize), T> {
+            type Inner = Secret<($usize, $usize, $usize, $usize), Option<T>>;
+            fn start() -> Self {AllWith(Secret {evidence: None, value: None})}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize, $usize), Option<T>>) -> bool {
+                if val.value.is_some() {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b, c)| (ind, a, b, c)),
+                        value: val.value
+                    };
+                    false
+                } else {
+                    true
+                }
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    }
+}
+
+all_with_impl!{usize}
+
+all_with_impl!{u32}
+
+all_with_impl!{u16}
@@ -2,68 +2,238 @@ use *;
 
 /// There exists loop.
 ///
-/// Returns `None` if there was no item in the loop satisfying the condition.
-/// Returns `Some(item)` if an item satisfies the condition.
-pub struct Any<I>(pub Option<I>);
+/// The value is `true` if an item in the loop satisfies the condition,
+/// with the index of the first such item as evidence.
+///
+/// When nesting loops, an inner loop might report `true` without evidence,
+/// e.g. a custom loop or a hand-built secret.
+/// The outer loop then reports `true` without evidence.
+#[derive(Debug, Clone)]
+pub struct Any<I>(pub Secret<I, bool>);
+
+impl<I> Default for Any<I> {
+    fn default() -> Any<I> {Any(Secret {evidence: None, value: false})}
+}
+
+impl<K> Lup<K, bool> for Any<K> {
+    type Inner = Secret<K, bool>;
+    fn start() -> Any<K> {Any::default()}
+    fn it(&mut self, ind: K, val: bool) -> bool {
+        if val {
+            self.0 = Secret {evidence: Some(ind), value: true};
+            false
+        } else {
+            true
+        }
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
 
 macro_rules! any_impl {
     ($usize:ty) => {
-        impl Lup<$usize, bool> for Any<$usize> {
-            type Inner = Secret<$usize, bool>;
-            fn start() -> Any<$usize> {Any(None)}
-            fn it(&mut self, ind: $usize, val: bool) -> bool {
-                if val {
-                    self.0 = Some(ind);
+        impl Lup<$usize, Secret<$usize, bool>> for Any<($usize, $usize)> {
+            type Inner = Secret<($usize, $usize), bool>;
+            fn start() -> Any<($usize, $usize)> {Any::default()}
+            fn it(&mut self, ind: $usize, val: Secret<$usize, bool>) -> bool {
+                if val.value {
+                    self.0 = Secret {evidence: val.evidence.map(|a| (ind, a)), value: true};
                     false
                 } else {
                     true
                 }
             }
-            fn unwrap(self) -> Self::Inner {
-                Secret {value: self.0.is_some(), evidence: self.0}
-            }
+            fn unwrap(self) -> Self::Inner {self.0}
         }
 
-        impl Lup<$usize, Secret<$usize, bool>> for Any<($usize, $usize)> {
-            type Inner = Secret<($usize, $usize), bool>;
-            fn start() -> Any<($usize, $usize)> {Any(None)}
-            fn it(&mut self, ind: $usize, val: Secret<$usize, bool>) -> bool {
+        impl<K: Key> Lup<$usize, Secret<K, bool>> for Any<($usize, K)> {
+            type Inner = Secret<($usize, K), bool>;
+            fn start() -> Any<($usize, K)> {Any::default()}
+            fn it(&mut self, ind: $usize, val: Secret<K, bool>) -> bool {
                 if val.value {
-                    if let Some(ind2) = val.evidence {
-                        self.0 = Some((ind, ind2));
-                        false
-                    } else {
-                        true
-                    }
+                    self.0 = Secret {evidence: val.evidence.map(|key| (ind, key)), value: true};
+                    false
                 } else {
                     true
                 }
             }
-            fn unwrap(self) -> Self::Inner {
-                Secret {value: self.0.is_some(), evidence: self.0}
-            }
+            fn unwrap(self) -> Self::Inner {self.0}
         }
 
         impl Lup<$usize, Secret<($usize, $usize), bool>> for Any<($usize, $usize, $usize)> {
             type Inner = Secret<($usize, $usize, $usize), bool>;
-            fn start() -> Any<($usize, $usize, $usize)> {Any(None)}
+            fn start() -> Any<($usize, $usize, $usize)> {Any::default()}
             fn it(&mut self, ind: $usize, val: Secret<($usize, $usize), bool>) -> bool {
                 if val.value {
-                    if let Some((a, b)) = val.evidence {
-                        self.0 = Some((ind, a, b));
-                        false
-                    } else {
-                        true
-                    }
+                    self.0 = Secret {evidence: val.evidence.map(|(a, b)| (ind, a, b)), value: true};
+                    false
                 } else {
                     true
                 }
             }
-            fn unwrap(self) -> Self::Inner {
-                Secret {value: self.0.is_some(), evidence: self.0}
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+
+        impl Lup<$usize, Secret<($usize, $usize, $usize), bool>>
+        for Any<($usize, $usize, $usize, $usize)> {
+            type Inner = Secret<($usize, $usize, $usize, $usize), bool>;
+            fn start() -> Any<($usize, $usize, $usize, $usize)> {Any::default()}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize, $usize), bool>) -> bool {
+                if val.value {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b, c)| (ind, a, b, c)),
+                        value: true
+                    };
+                    false
+                } else {
+                    true
+                }
             }
+            fn unwrap(self) -> Self::Inner {self.0}
         }
     }
 }
 
 any_impl!{usize}
+
+any_impl!{u32}
+
+any_impl!{u16}
+
+/// There exists loop that also counts the scanned items.
+///
+/// Like `Any`, but the result also tells how many items were checked
+/// before and including the first match, or in total when there was none.
+/// When nesting loops, the scanned items of the inner loops are summed.
+#[derive(Debug, Clone)]
+pub struct AnyScan<I>(pub Any<I>, pub usize);
+
+impl<I> Default for AnyScan<I> {
+    fn default() -> AnyScan<I> {AnyScan(Any::default(), 0)}
+}
+
+/// The result of an `AnyScan` loop.
+#[derive(Debug, Clone)]
+pub struct Scanned<E> {
+    /// The result of the `Any` loop.
+    pub secret: Secret<E, bool>,
+    /// The number of scanned items.
+    pub scanned: usize,
+}
+
+impl<K> Lup<K, bool> for AnyScan<K> {
+    type Inner = Scanned<K>;
+    fn start() -> AnyScan<K> {AnyScan::default()}
+    fn it(&mut self, ind: K, val: bool) -> bool {
+        self.1 += 1;
+        self.0.it(ind, val)
+    }
+    fn unwrap(self) -> Scanned<K> {
+        Scanned {secret: self.0.unwrap(), scanned: self.1}
+    }
+}
+
+impl<I, E, K> Lup<I, Scanned<E>> for AnyScan<K>
+    where Any<K>: Lup<I, Secret<E, bool>, Inner = Secret<K, bool>>
+{
+    type Inner = Scanned<K>;
+    fn start() -> AnyScan<K> {AnyScan::default()}
+    fn it(&mut self, ind: I, val: Scanned<E>) -> bool {
+        self.1 += val.scanned;
+        self.0.it(ind, val.secret)
+    }
+    fn unwrap(self) -> Scanned<K> {
+        Scanned {secret: self.0.unwrap(), scanned: self.1}
+    }
+}
+
+/// The result of an `AnyBudget` loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchResult<I> {
+    /// An item satisfies the condition, at the given index.
+    Found(I),
+    /// The budget ran out before a match was found.
+    Exhausted,
+    /// All items were checked, and none satisfies the condition.
+    NotFound,
+}
+
+/// There exists loop that stops after a maximum number of evaluations.
+///
+/// The loop is constructed with the budget using the constructor syntax of `lup!`,
+/// e.g. `lup!(AnyBudget(1000): i in 0..n => {...})`.
+///
+/// The budget is a hard cap: the loop stops once it is spent,
+/// so the body is never evaluated more than `budget` times.
+/// The result is `Exhausted` when the budget is spent without a match,
+/// even if there were no more items, since the loop does not look at them.
+#[derive(Debug, Clone)]
+pub struct AnyBudget<I> {
+    /// The result so far.
+    pub result: SearchResult<I>,
+    /// The maximum number of evaluations.
+    pub budget: usize,
+    /// The number of evaluations so far.
+    pub evaluations: usize,
+}
+
+impl<I> AnyBudget<I> {
+    /// Creates a new loop with a budget.
+    pub fn new(budget: usize) -> AnyBudget<I> {
+        AnyBudget {result: SearchResult::NotFound, budget, evaluations: 0}
+    }
+}
+
+impl<K> Lup<K, bool> for AnyBudget<K> {
+    type Inner = SearchResult<K>;
+    fn start() -> AnyBudget<K> {AnyBudget::new(usize::MAX)}
+    fn it(&mut self, ind: K, val: bool) -> bool {
+        if self.evaluations >= self.budget {return false}
+        self.evaluations += 1;
+        if val {
+            self.result = SearchResult::Found(ind);
+            false
+        } else {
+            self.evaluations < self.budget
+        }
+    }
+    fn unwrap(self) -> SearchResult<K> {
+        let evaluations = self.evaluations;
+        self.finish(evaluations)
+    }
+    fn finish(self, iterations: usize) -> SearchResult<K> {
+        match self.result {
+            SearchResult::NotFound if iterations >= self.budget => SearchResult::Exhausted,
+            result => result,
+        }
+    }
+}
+
+impl<K> LupWith<usize, K, bool> for AnyBudget<K> {
+    fn start_with(budget: usize) -> AnyBudget<K> {AnyBudget::new(budget)}
+}
+
+/// There exists loop with the matched item as evidence.
+///
+/// The body returns `(condition, item)`.
+/// The value is `true` if an item satisfies the condition,
+/// with the first such item as evidence instead of its index.
+#[derive(Debug, Clone)]
+pub struct ValueAny<T>(pub Secret<T, bool>);
+
+impl<T> Default for ValueAny<T> {
+    fn default() -> ValueAny<T> {ValueAny(Secret {evidence: None, value: false})}
+}
+
+impl<I, T> Lup<I, (bool, T)> for ValueAny<T> {
+    type Inner = Secret<T, bool>;
+    fn start() -> ValueAny<T> {ValueAny::default()}
+    fn it(&mut self, _ind: I, (cond, item): (bool, T)) -> bool {
+        if cond {
+            self.0 = Secret {evidence: Some(item), value: true};
+            false
+        } else {
+            true
+        }
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
@@ -0,0 +1,68 @@
+use *;
+
+/// Loop that applies the body values to existing state.
+///
+/// The loop is constructed with a mutable reference to the state
+/// and an update function, using the constructor syntax of `lup!`.
+/// The update function gets the state and the body value,
+/// and the loop continues while it returns `true`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Apply;
+///
+/// fn main() {
+///     let sprites = ["a", "b", "c", "d"];
+///     let mut batch: Vec<&str> = vec![];
+///     lup!(Apply(&mut batch, |batch: &mut Vec<_>, s| {batch.push(s); batch.len() < 3}):
+///          i by sprites => {sprites[i]});
+///     println!("{:?}", batch); // Prints `["a", "b", "c"]`.
+/// }
+/// ```
+///
+/// The state can not be used in the body, since the loop borrows it mutably.
+/// The result is `()`.
+///
+/// Starting the loop without a constructor fails to compile:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Apply;
+///
+/// fn main() {
+///     let sprites = ["a", "b"];
+///     lup!(Apply<Vec<&str>, fn(&mut Vec<&str>, &str) -> bool>: i by sprites => {sprites[i]});
+/// }
+/// ```
+pub struct Apply<'a, S: 'a, F>(pub &'a mut S, pub F);
+
+impl<'a, S, F> Apply<'a, S, F> {
+    /// Creates a new loop applying values to the state.
+    pub fn new<T>(state: &'a mut S, f: F) -> Apply<'a, S, F>
+        where F: FnMut(&mut S, T) -> bool
+    {
+        Apply(state, f)
+    }
+}
+
+/// Fails to compile when used, since there is no state to start `Apply` with.
+struct NoStart<S, F>(PhantomData<(S, F)>);
+
+impl<S, F> NoStart<S, F> {
+    const ERROR: () = panic!("Apply: expected a constructor, e.g. `Apply(&mut state, update)`");
+}
+
+impl<'a, I, T, S, F: FnMut(&mut S, T) -> bool> Lup<I, T> for Apply<'a, S, F> {
+    type Inner = ();
+    fn start() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = NoStart::<S, F>::ERROR;
+        unreachable!()
+    }
+    fn it(&mut self, _ind: I, val: T) -> bool {(self.1)(self.0, val)}
+    fn unwrap(self) -> Self::Inner {}
+}
@@ -0,0 +1,48 @@
+use *;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Bitmask loop.
+///
+/// Sets bit `ind` when the body returns `true`.
+/// Supports `u64`, `u128` and `Vec<u64>` (requires `alloc`).
+/// The vector grows to fit the highest set bit.
+///
+/// Panics if the index does not fit in the bitmask.
+#[derive(Debug, Clone, Default)]
+pub struct BitMask<T>(pub T);
+
+macro_rules! bit_mask_impl {
+    ($u64:ty) => {
+        impl Lup<usize, bool> for BitMask<$u64> {
+            type Inner = $u64;
+            fn start() -> Self {BitMask(0)}
+            fn it(&mut self, ind: usize, val: bool) -> bool {
+                let bits = 8 * core::mem::size_of::<$u64>();
+                assert!(ind < bits, "BitMask: index {} out of range for {} bits", ind, bits);
+                if val {self.0 |= 1 << ind}
+                true
+            }
+            fn unwrap(self) -> $u64 {self.0}
+        }
+    }
+}
+
+bit_mask_impl!{u64}
+
+bit_mask_impl!{u128}
+
+#[cfg(feature = "alloc")]
+impl Lup<usize, bool> for BitMask<Vec<u64>> {
+    type Inner = Vec<u64>;
+    fn start() -> Self {BitMask(Vec::new())}
+    fn it(&mut self, ind: usize, val: bool) -> bool {
+        if val {
+            let word = ind / 64;
+            if word >= self.0.len() {self.0.resize(word + 1, 0)}
+            self.0[word] |= 1 << (ind % 64);
+        }
+        true
+    }
+    fn unwrap(self) -> Vec<u64> {self.0}
+}
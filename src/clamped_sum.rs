@@ -0,0 +1,52 @@
+use *;
+
+/// Sum loop clamped to a maximum.
+///
+/// The value is the sum, clamped to the cap,
+/// and the evidence is the index where the cap was first reached.
+/// The loop stops there.
+///
+/// The loop is constructed with the cap using the constructor syntax of `lup!`,
+/// e.g. `lup!(ClampedSum(100): i by damage => {damage[i]})`.
+/// When started without a constructor, the cap is the default value, i.e. zero.
+///
+/// Integer sums saturate instead of overflowing.
+#[derive(Debug, Clone)]
+pub struct ClampedSum<T>(pub Secret<usize, T>, pub T);
+
+impl<T: Default> ClampedSum<T> {
+    /// Creates a new loop with a cap.
+    pub fn new(cap: T) -> ClampedSum<T> {
+        ClampedSum(Secret {evidence: None, value: T::default()}, cap)
+    }
+}
+
+macro_rules! clamped_sum_impl {
+    ($add:ident : $($t:ty),*) => {$(
+        impl Lup<usize, $t> for ClampedSum<$t> {
+            type Inner = Secret<usize, $t>;
+            fn start() -> Self {ClampedSum::new(Default::default())}
+            fn it(&mut self, ind: usize, val: $t) -> bool {
+                let sum = clamped_sum_impl!(@$add self.0.value, val);
+                if sum >= self.1 {
+                    self.0 = Secret {evidence: Some(ind), value: self.1};
+                    false
+                } else {
+                    self.0.value = sum;
+                    true
+                }
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+
+        impl LupWith<$t, usize, $t> for ClampedSum<$t> {
+            fn start_with(cap: $t) -> Self {ClampedSum::new(cap)}
+        }
+    )*};
+    (@add $a:expr, $b:expr) => {$a + $b};
+    (@saturating_add $a:expr, $b:expr) => {$a.saturating_add($b)};
+}
+
+clamped_sum_impl!{add: f32, f64}
+
+clamped_sum_impl!{saturating_add: u8, u16, u32, u64, usize, i8, i16, i32, i64, isize}
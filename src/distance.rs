@@ -0,0 +1,78 @@
+use *;
+
+/// L1 distance loop over paired values.
+///
+/// The body returns `(a, b)` pairs and the loop sums `|a - b|`.
+/// Like `Sum`, a NaN difference makes the result NaN.
+#[derive(Debug, Clone, Default)]
+pub struct L1(pub f64);
+
+impl<I> Lup<I, (f64, f64)> for L1 {
+    type Inner = f64;
+    fn start() -> L1 {L1(0.0)}
+    fn it(&mut self, _ind: I, (a, b): (f64, f64)) -> bool {
+        self.0 += if a > b {a - b} else {b - a};
+        true
+    }
+    fn unwrap(self) -> f64 {self.0}
+}
+
+/// L-infinity distance loop over paired values.
+///
+/// The body returns `(a, b)` pairs and the loop finds the maximum of `|a - b|`.
+/// The evidence is the index of the first pair with the maximum difference.
+///
+/// Pairs with a NaN difference are skipped.
+/// Returns `0.0` without evidence when there are no other pairs.
+#[derive(Debug, Clone)]
+pub struct Linf<I>(pub Secret<I, f64>);
+
+impl<I> Default for Linf<I> {
+    fn default() -> Linf<I> {Linf(Secret {evidence: None, value: 0.0})}
+}
+
+impl<I> Lup<I, (f64, f64)> for Linf<I> {
+    type Inner = Secret<I, f64>;
+    fn start() -> Linf<I> {Linf::default()}
+    fn it(&mut self, ind: I, (a, b): (f64, f64)) -> bool {
+        let d = if a > b {a - b} else {b - a};
+        if d.is_nan() {return true}
+        if self.0.evidence.is_none() || d > self.0.value {
+            self.0 = Secret {evidence: Some(ind), value: d};
+        }
+        true
+    }
+    fn unwrap(self) -> Secret<I, f64> {self.0}
+}
+
+/// Hamming distance loop over paired values.
+///
+/// The body returns `(a, b)` pairs and the loop counts the pairs that differ.
+#[derive(Debug, Clone, Default)]
+pub struct Hamming(pub usize);
+
+impl<I, T: Eq> Lup<I, (T, T)> for Hamming {
+    type Inner = usize;
+    fn start() -> Hamming {Hamming(0)}
+    fn it(&mut self, _ind: I, (a, b): (T, T)) -> bool {
+        if a != b {self.0 += 1}
+        true
+    }
+    fn unwrap(self) -> usize {self.0}
+}
+
+/// Hamming distance loop over packed bitsets.
+///
+/// The body returns `(a, b)` pairs of words and the loop counts the bits that differ.
+#[derive(Debug, Clone, Default)]
+pub struct HammingBits(pub usize);
+
+impl<I> Lup<I, (u64, u64)> for HammingBits {
+    type Inner = usize;
+    fn start() -> HammingBits {HammingBits(0)}
+    fn it(&mut self, _ind: I, (a, b): (u64, u64)) -> bool {
+        self.0 += (a ^ b).count_ones() as usize;
+        true
+    }
+    fn unwrap(self) -> usize {self.0}
+}
@@ -0,0 +1,58 @@
+use *;
+
+/// A predicate on body values, used by `FilterLup`.
+///
+/// Implemented by zero-sized marker types,
+/// so the filtered loop can be started without arguments.
+pub trait FilterFn<T> {
+    /// Returns `true` if the value should be passed to the inner loop.
+    fn accept(val: &T) -> bool;
+}
+
+/// Accepts values greater than zero.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Positive;
+
+/// Accepts values that are neither infinite nor NaN.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Finite;
+
+macro_rules! filter_fn_impl {
+    ($f32:ty) => {
+        impl FilterFn<$f32> for Positive {
+            fn accept(val: &$f32) -> bool {*val > 0.0}
+        }
+
+        impl FilterFn<$f32> for Finite {
+            fn accept(val: &$f32) -> bool {val.is_finite()}
+        }
+    }
+}
+
+filter_fn_impl!{f32}
+
+filter_fn_impl!{f64}
+
+/// Loop that only passes the body values accepted by a predicate to an inner loop.
+///
+/// For example, `FilterLup<Sum<f64>, Finite>` ignores infinite and NaN values.
+/// The iterations passed to the inner loop when it finishes
+/// only count the accepted values.
+/// The size hint is not passed on, since any number of values can be rejected.
+#[derive(Debug, Clone)]
+pub struct FilterLup<L, P>(pub L, pub usize, pub PhantomData<P>);
+
+impl<I, T, P: FilterFn<T>, L: Lup<I, T>> Lup<I, T> for FilterLup<L, P> {
+    type Inner = L::Inner;
+    fn start() -> Self {FilterLup(L::start(), 0, PhantomData)}
+    fn it(&mut self, ind: I, val: T) -> bool {
+        if P::accept(&val) {
+            self.1 += 1;
+            self.0.it(ind, val)
+        } else {
+            true
+        }
+    }
+    fn unwrap(self) -> Self::Inner {self.0.unwrap()}
+    fn finish(self, _iterations: usize) -> Self::Inner {self.0.finish(self.1)}
+}
@@ -0,0 +1,40 @@
+/// Controls a loop from its body.
+///
+/// Used with the `=> flow {<body>}` syntax of `lup!`,
+/// where the body returns a `Flow` instead of a value:
+///
+/// - `Continue(value)` passes the value to the loop
+/// - `Skip` passes nothing and goes on with the next item
+/// - `Break(value)` passes the value to the loop and stops it
+///
+/// With several packed indices, `Break` stops the loops at every level:
+/// the inner loop is unwrapped and passed to the outer loop,
+/// which then stops too.
+/// A loop where every item was skipped passes its start value to the outer loop.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::{Flow, Sum};
+///
+/// fn main() {
+///     let xs = [1.0, -2.0, 3.0, 0.0, 5.0];
+///     // Sum positive values until the first zero.
+///     let sum = lup!(Sum<f64>: i by xs => flow {
+///         if xs[i] == 0.0 {Flow::Break(0.0)}
+///         else if xs[i] < 0.0 {Flow::Skip}
+///         else {Flow::Continue(xs[i])}
+///     });
+///     println!("{}", sum); // Prints `4`.
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow<T> {
+    /// Passes the value to the loop and continues.
+    Continue(T),
+    /// Skips the item.
+    Skip,
+    /// Passes the value to the loop and stops.
+    Break(T),
+}
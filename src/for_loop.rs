@@ -1,12 +1,32 @@
 use *;
 
 /// Indexed for-loop.
+#[derive(Debug, Clone, Default)]
 pub struct For;
 
-impl Lup<usize, ()> for For {
+impl<I> Lup<I, ()> for For {
     type Inner = ();
 
     fn start() -> Self {For}
-    fn it(&mut self, _ind: usize, _val: ()) -> bool {true}
+    fn it(&mut self, _ind: I, _val: ()) -> bool {true}
     fn unwrap(self) -> Self::Inner {}
 }
+
+/// Indexed for-each loop where the body controls continuation.
+///
+/// The loop continues while the body returns `true`.
+/// The result is the number of iterations executed,
+/// including the one that returned `false`.
+#[derive(Debug, Clone, Default)]
+pub struct ForEach(pub usize);
+
+impl Lup<usize, bool> for ForEach {
+    type Inner = usize;
+
+    fn start() -> Self {ForEach(0)}
+    fn it(&mut self, _ind: usize, val: bool) -> bool {
+        self.0 += 1;
+        val
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
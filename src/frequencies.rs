@@ -0,0 +1,58 @@
+use *;
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+
+/// Frequency loop.
+///
+/// The body returns a key, and the result maps each key
+/// to the number of times it occurred.
+/// The result iterates over the keys in the order they first occurred,
+/// so it does not depend on the hasher,
+/// which can be chosen with the second type parameter.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Frequencies<K, H = RandomState>(pub OrderMap<K, usize, H>);
+
+#[cfg(feature = "std")]
+impl<K: Eq + Hash, H: BuildHasher + Default> Default for Frequencies<K, H> {
+    fn default() -> Frequencies<K, H> {Frequencies(OrderMap::default())}
+}
+
+#[cfg(feature = "std")]
+impl<I, K: Eq + Hash, H: BuildHasher + Default> Lup<I, K> for Frequencies<K, H> {
+    type Inner = OrderMap<K, usize, H>;
+    fn start() -> Self {Frequencies::default()}
+    fn it(&mut self, _ind: I, val: K) -> bool {
+        *self.0.get_or_insert_with(val, || 0) += 1;
+        true
+    }
+    fn unwrap(self) -> OrderMap<K, usize, H> {self.0}
+}
+
+/// Frequency loop with keys in sorted order.
+///
+/// Like `Frequencies`, but backed by a `BTreeMap`,
+/// so the result iterates over the keys in sorted order.
+///
+/// Requires the `alloc` feature.
+#[derive(Debug, Clone)]
+pub struct SortedFrequencies<K>(pub BTreeMap<K, usize>);
+
+impl<K: Ord> Default for SortedFrequencies<K> {
+    fn default() -> SortedFrequencies<K> {SortedFrequencies(BTreeMap::new())}
+}
+
+impl<I, K: Ord> Lup<I, K> for SortedFrequencies<K> {
+    type Inner = BTreeMap<K, usize>;
+    fn start() -> Self {SortedFrequencies::default()}
+    fn it(&mut self, _ind: I, val: K) -> bool {
+        *self.0.entry(val).or_insert(0) += 1;
+        true
+    }
+    fn unwrap(self) -> BTreeMap<K, usize> {self.0}
+}
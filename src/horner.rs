@@ -0,0 +1,46 @@
+use *;
+
+/// Polynomial evaluation loop using Horner's method.
+///
+/// Evaluates a polynomial at `x`.
+/// The body returns the coefficients, highest degree first,
+/// and each step computes `acc * x + c`.
+///
+/// The loop is constructed with `x` using the constructor syntax of `lup!`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Horner;
+///
+/// fn main() {
+///     // 2x^2 - 3x + 1
+///     let coeffs = [2.0, -3.0, 1.0];
+///     let y = lup!(Horner(4.0): i by coeffs => {coeffs[i]});
+///     println!("{}", y); // Prints `21`.
+/// }
+/// ```
+///
+/// When started without a constructor, `x` is zero.
+#[derive(Debug, Clone, Default)]
+pub struct Horner(pub f64, pub f64);
+
+impl Horner {
+    /// Creates a new loop evaluating a polynomial at `x`.
+    pub fn new(x: f64) -> Horner {Horner(x, 0.0)}
+}
+
+impl<I> Lup<I, f64> for Horner {
+    type Inner = f64;
+    fn start() -> Horner {Horner::new(0.0)}
+    fn it(&mut self, _ind: I, c: f64) -> bool {
+        self.1 = self.1 * self.0 + c;
+        true
+    }
+    fn unwrap(self) -> f64 {self.1}
+}
+
+impl<I> LupWith<f64, I, f64> for Horner {
+    fn start_with(x: f64) -> Horner {Horner::new(x)}
+}
@@ -0,0 +1,26 @@
+use *;
+
+/// Drives custom loops from iterators.
+///
+/// The items are enumerated and passed to the loop with their position as index,
+/// so loops with evidence tell where in the iterator the evidence was found.
+///
+/// ```
+/// use lup::{Any, LupIterator};
+///
+/// let words = vec!["mary", "had", "a", "little", "lamb"];
+/// let lamb = words.iter().map(|w| w == &"lamb").lup::<Any<usize>>();
+/// assert_eq!(lamb.evidence, Some(4));
+/// ```
+pub trait LupIterator: Iterator + Sized {
+    /// Runs a custom loop over the items of the iterator.
+    fn lup<L: Lup<usize, Self::Item>>(self) -> L::Inner {
+        let mut lup: Driver<L, usize, Self::Item> = Driver::start_for(&self);
+        for (ind, val) in self.enumerate() {
+            if !lup.it(ind, val) {break};
+        }
+        lup.unwrap()
+    }
+}
+
+impl<It: Iterator> LupIterator for It {}
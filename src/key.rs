@@ -0,0 +1,21 @@
+/// Implemented by keys that can be used as inner evidence of nested loops.
+///
+/// The built-in loops that return evidence (`Any`, `All`, `Max` and `Min`)
+/// can wrap an inner loop over keys of type `K` in an outer loop over indices,
+/// giving evidence `(usize, K)`.
+/// This is used e.g. when the inner loop iterates the keys of a map.
+///
+/// Integer types do not implement `Key`,
+/// because nested loops over indices use flat evidence tuples instead,
+/// and integer literals used as indices must still be inferred as `usize`.
+/// Implement it for your own key types to use them as inner evidence.
+pub trait Key: Clone {}
+
+impl Key for char {}
+
+impl Key for bool {}
+
+impl Key for &str {}
+
+#[cfg(feature = "alloc")]
+impl Key for alloc::string::String {}
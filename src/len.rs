@@ -0,0 +1,32 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+
+/// Implemented by containers that can be used with the `by` syntax.
+///
+/// The `lup!` macro loops from `0` to `lup_len` and indexes the container.
+/// Custom containers implementing `Index<usize>` opt in with an impl of this trait.
+/// Containers that dereference to a slice, such as `Vec`, work without an impl.
+pub trait Len {
+    /// Returns the number of items.
+    fn lup_len(&self) -> usize;
+}
+
+impl<T> Len for [T] {
+    fn lup_len(&self) -> usize {self.len()}
+}
+
+impl<T, const N: usize> Len for [T; N] {
+    fn lup_len(&self) -> usize {N}
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Len for Vec<T> {
+    fn lup_len(&self) -> usize {self.len()}
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Len for VecDeque<T> {
+    fn lup_len(&self) -> usize {self.len()}
+}
@@ -112,7 +112,8 @@
 //! }
 //! ```
 //!
-//! We convert to `f32` since `Max` is implemented only for `f32` and `f64`.
+//! We convert to `f32` since `Max` is implemented for types implementing `Scalar`,
+//! such as `f32` and `f64`.
 //!
 //! The evidence points to the item that first achieves maximum value.
 //!
@@ -151,31 +152,160 @@
 //!
 //! The example calls `.le` instead of using `<=` because Rust does not allow
 //! overriding the return value of comparison operators.
+//!
+//! ### no_std
+//!
+//! The crate supports `no_std` by disabling the default `std` feature.
+//! Loops that allocate, such as `Sift` and `Link`, require the `alloc` feature.
 
 
 #![deny(missing_docs)]
+#![no_std]
+
+#[cfg(any(feature = "std", test))]
+#[macro_use]
+extern crate std;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "nalgebra")]
+extern crate nalgebra;
+#[cfg(feature = "vecmath")]
+extern crate vecmath;
+#[cfg(feature = "half")]
+extern crate half;
+#[cfg(feature = "bigint")]
+extern crate num_bigint;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "glam")]
+extern crate glam;
+#[cfg(feature = "mint")]
+extern crate mint;
+
+use core::fmt::Debug;
+use core::marker::PhantomData;
 
-pub use sum::Sum;
-pub use prod::Prod;
-pub use any::Any;
-pub use all::All;
-pub use max::Max;
-pub use min::Min;
-pub use vector::Vector;
-pub use sift::Sift;
+pub use sum::{Sum, SumOpt, SumUnordered, SumWide, Tally};
+#[cfg(feature = "half")]
+pub use sum::HalfSum;
+pub use prod::{Prod, ProdChecked};
+pub use any::{Any, AnyBudget, AnyScan, Scanned, SearchResult, ValueAny};
+pub use all::{All, AllChecked, AllPairs, Checked};
+pub use all_with::AllWith;
+pub use max::{Max, MaxOpt};
+pub use max_item::MaxItem;
+pub use max_ref::{MaxRef, MinRef};
+pub use strict::{MaxStrict, MinStrict, NanAt};
+pub use min::{Min, MinOpt, MinPair};
+pub use per_axis::{MaxPerAxis, MinPerAxis};
+pub use vector::{Vector, VectorFrom};
+#[cfg(feature = "alloc")]
+pub use sift::{Sift, SiftIndexed, SparseSift, SortedUniqueSift, CumProd, FlatVector, Normalize};
+#[cfg(feature = "std")]
+pub use sift::{Softmax, ZScore};
+#[cfg(feature = "alloc")]
+pub use link::Link;
 pub use secret::Secret;
-pub use for_loop::For;
+pub use scalar::Scalar;
+pub use key::Key;
+pub use len::Len;
+pub use for_loop::{For, ForEach};
+pub use apply::Apply;
+pub use until::Until;
+pub use flow::Flow;
+pub use horner::Horner;
+pub use clamped_sum::ClampedSum;
+pub use bit_mask::BitMask;
+pub use unordered::UnorderedAccum;
+#[doc(hidden)]
+pub use unordered::{OrderedMerge, UnorderedMerge};
+pub use sorted::{IsSorted, IsStrictlySorted};
+pub use run_length::LongestRun;
+pub use majority::Majority;
+pub use distance::{L1, Linf, Hamming, HammingBits};
+#[cfg(feature = "std")]
+pub use timed::{Timed, Timing};
+pub use map_lup::{MapLup, MapFn, Abs, Sq, Recip};
+pub use filter_lup::{FilterLup, FilterFn, Positive, Finite};
+pub use skip_take::{SkipLup, TakeLup, MaxIter, Bounded};
+#[cfg(feature = "std")]
+pub use unique::IsUnique;
+#[cfg(feature = "alloc")]
+pub use frequencies::SortedFrequencies;
+#[cfg(feature = "std")]
+pub use frequencies::Frequencies;
+#[cfg(feature = "std")]
+pub use order_map::OrderMap;
+pub use stats::{Covariance, MinGap, P2Quantile, Spread, Summary};
+#[cfg(feature = "alloc")]
+pub use stats::Percentile;
+#[cfg(feature = "std")]
+pub use stats::{Correlation, Rms, Entropy, EntropyBits};
+pub use prove::{Exists, Forall, Counterexample};
+pub use tiles::Tiles;
+pub use run::{run, any, all, max, min, sum};
+pub use iterator::LupIterator;
+pub use shape::Shape2;
+pub use mat_mul::MatMul;
+pub use quat_prod::QuatProd;
+#[cfg(feature = "vecmath")]
+pub use mat_mul_prod::MatMulProd;
 
 mod sum;
 mod prod;
 mod any;
 mod all;
+mod all_with;
 mod max;
+mod max_item;
+mod max_ref;
+mod strict;
 mod min;
+mod per_axis;
 mod vector;
+#[cfg(feature = "alloc")]
 mod sift;
+#[cfg(feature = "alloc")]
+mod link;
 mod secret;
+mod scalar;
+mod key;
+mod len;
 mod for_loop;
+mod apply;
+mod until;
+mod flow;
+mod horner;
+mod clamped_sum;
+mod bit_mask;
+mod unordered;
+mod sorted;
+mod run_length;
+mod majority;
+mod distance;
+#[cfg(feature = "std")]
+mod timed;
+mod map_lup;
+mod filter_lup;
+mod skip_take;
+#[cfg(feature = "std")]
+mod unique;
+#[cfg(feature = "alloc")]
+mod frequencies;
+#[cfg(feature = "std")]
+mod order_map;
+mod stats;
+mod prove;
+mod tiles;
+mod run;
+mod iterator;
+mod shape;
+mod mat_mul;
+mod quat_prod;
+#[cfg(feature = "vecmath")]
+mod mat_mul_prod;
 
 /// Implemented by custom loops.
 pub trait Lup<I, T> {
@@ -183,16 +313,425 @@ pub trait Lup<I, T> {
     type Inner;
     /// Initialize loop.
     fn start() -> Self;
+    /// Initialize loop with the number of iterations, if known in advance.
+    ///
+    /// The `lup!` macro passes the exact length of ranges and `by` lists,
+    /// so loops that collect items can reserve storage.
+    /// Calls `start` by default.
+    fn start_sized(hint: Option<usize>) -> Self where Self: Sized {
+        let _ = hint;
+        Self::start()
+    }
     /// Iterate loop.
-    fn it(&mut self, I, T) -> bool;
+    fn it(&mut self, ind: I, val: T) -> bool;
     /// Unwrap the resulting value.
     fn unwrap(self) -> Self::Inner;
+    /// Unwrap the resulting value, knowing how many times `it` was called.
+    ///
+    /// The `lup!` macro calls this at the end of the loop,
+    /// including the iteration that stopped the loop early.
+    /// Calls `unwrap` by default.
+    fn finish(self, iterations: usize) -> Self::Inner where Self: Sized {
+        let _ = iterations;
+        self.unwrap()
+    }
+}
+
+/// Implemented by custom loops that are started with a context.
+///
+/// The `lup!` macro calls `start_with` for the syntax `<type> with <context>: ...`,
+/// e.g. `lup!(Sum<f64> with 10.0: i by xs => {xs[i]})` starts the sum at `10.0`.
+pub trait LupWith<Ctx, I, T>: Lup<I, T> {
+    /// Initialize loop with a context.
+    fn start_with(ctx: Ctx) -> Self where Self: Sized;
+}
+
+/// Drives a custom loop on behalf of the `lup!` macro.
+///
+/// Loops can implement `Lup` for several index and body types,
+/// so the driver keeps track of which ones are used by `it`
+/// when calling `start` and `unwrap`.
+/// It also counts the iterations for `finish`.
+#[doc(hidden)]
+pub struct Driver<L, I, T>(L, usize, PhantomData<fn(I, T)>);
+
+impl<L: Lup<I, T>, I, T> Driver<L, I, T> {
+    /// Initialize loop with the number of items in the iterator, if known.
+    pub fn start_for<It: Iterator>(iter: &It) -> Self {
+        Driver(L::start_sized(exact_len(iter)), 0, PhantomData)
+    }
+    /// Drive a loop that is already constructed.
+    pub fn new(lup: L) -> Self {Driver(lup, 0, PhantomData)}
+    /// Initialize loop with a context.
+    pub fn start_with<Ctx>(ctx: Ctx) -> Self where L: LupWith<Ctx, I, T> {
+        Driver(L::start_with(ctx), 0, PhantomData)
+    }
+    /// Iterate loop.
+    pub fn it(&mut self, ind: I, val: T) -> bool {
+        self.1 += 1;
+        self.0.it(ind, val)
+    }
+    /// Unwrap the resulting value.
+    pub fn unwrap(self) -> L::Inner {self.0.finish(self.1)}
 }
 
+impl<L: UnorderedAccum<I, T>, I, T> Driver<L, I, T> {
+    /// Merge a partial loop into this one.
+    pub fn merge(&mut self, other: Self) {
+        self.1 += other.1;
+        self.0.merge(other.0);
+    }
+}
+
+/// Converts the iterator of the `in` syntax, which can be any `IntoIterator`.
+#[doc(hidden)]
+pub fn into_iter<It: IntoIterator>(iter: It) -> It::IntoIter {iter.into_iter()}
+
+/// Returns no item, with the item type of an iterator.
+///
+/// Lets `lup!` type check the body before choosing how to run the loop.
+#[doc(hidden)]
+pub fn no_item<It: Iterator>(_iter: &It) -> Option<It::Item> {None}
+
+/// Iterates over the unordered index pairs `(i, j)` with `i < j < n`.
+#[doc(hidden)]
+pub fn pairs(n: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..n).flat_map(move |i| (i + 1..n).map(move |j| (i, j)))
+}
+
+/// Panics with the message of `assert_lup!`.
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_lup_failed<E: Debug, T: Debug>(evidence: Option<&E>, item: Option<&T>) -> ! {
+    match (evidence, item) {
+        (Some(e), Some(item)) => panic!("assert_lup! failed: counterexample at {:?}: {:?}", e, item),
+        (Some(e), None) => panic!("assert_lup! failed: counterexample at {:?}", e),
+        (None, _) => panic!("assert_lup! failed: no witness found"),
+    }
+}
+
+/// Returns the number of items in an iterator, if its size hint is exact.
+fn exact_len<It: Iterator>(iter: &It) -> Option<usize> {
+    match iter.size_hint() {
+        (lower, Some(upper)) if lower == upper => Some(lower),
+        _ => None,
+    }
+}
+
+/// Runs a custom loop.
+///
+/// The syntax is `lup!(<loop type>: <indices> => {<body>})`,
+/// where the indices are one of:
+///
+/// - `i in <iterator>` loops over the items of an iterator,
+///   or anything implementing `IntoIterator` such as `&vec`
+/// - `i by <list>` loops over the range `0..list.len()`
+/// - `k of <map>` loops over clones of the keys of a map
+/// - `(i, v) in <iterator>` loops over `(index, item)` pairs,
+///   passing the index to the loop and binding the item in the body
+/// - `i, j by2 <matrix>` loops over the rows and columns of a `Shape2` container,
+///   such as `ndarray::Array2` with the `ndarray` feature
+/// - `w window <n> by <list>` loops over the windows of length `n` in a list,
+///   binding the range `w` of each window and passing its start to the loop
+/// - `i in <from> downto <to>` counts down from `from` to `to`, both inclusive
+/// - `(i, j) by <list>` loops over the unordered pairs of indices with `i < j`,
+///   passing `(i, j)` to the loop, e.g. for `AllPairs`
+///
+/// The index can be `_` when the body does not use it.
+///
+/// The list of `by` can be any container implementing `Len` and `Index<usize>`.
+///
+/// The indices of `by` are `usize` by default.
+/// A smaller index type can be given with `: <type>` before `by`,
+/// e.g. `i, j: u32 by list =>`, which stores less memory per evidence.
+/// The length of the list is cast to the index type,
+/// so it is the caller's responsibility that it fits.
+/// With `in`, the index type follows from the iterator, e.g. `i in 0u32..n`.
+///
+/// Several `in` or `by` indices separated by commas are packed into nested loops.
+///
+/// A `by` list can be followed by `skip <n>` and/or `take <n>`,
+/// e.g. `i by items skip 100 take 20 =>`, to loop over a part of the list.
+/// The range is clamped to the length of the list
+/// and the indices are positions in the whole list.
+///
+/// A `by` list can instead be followed by `offset <n>`,
+/// e.g. `i by data[100..200] offset 100 =>`, when the list is a part of a larger one.
+/// The body sees indices into the list, while the loop and its evidence
+/// get the indices shifted by `n`, i.e. positions in the larger list.
+///
+/// Per-iteration bindings can be added before the body with `with`,
+/// e.g. `i by xs with d = xs[i] - mean, d2 = d * d =>`.
+/// The bindings can use the indices and earlier bindings.
+///
+/// The type of the body can be given with `-> <type>` after the loop type,
+/// e.g. `lup!(Max<_, _> -> f32: i by xs => {xs[i].into()})`,
+/// which helps type inference when the body is generic.
+///
+/// The body can control the loop with `=> flow {<body>}`,
+/// returning a `Flow` to skip items or stop the loop at any level.
+/// This works with `in` and `by` indices.
+///
+/// The braces around the body can be left out for expressions,
+/// e.g. `lup!(Sum<f64>: i by xs => xs[i])`.
+///
+/// Common mistakes are reported with a targeted error message:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate lup;
+/// # use lup::Sum;
+/// # fn main() {
+/// # let xs = [1.0, 2.0];
+/// // error: lup!: expected indices such as `i in <iterator>` or `i by <list>` before `=>`
+/// let sum = lup!(Sum<f64>: i at xs => {xs[i]});
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate lup;
+/// # use lup::Sum;
+/// # fn main() {
+/// # let xs = [1.0, 2.0];
+/// // error: lup!: expected `=>` before the body
+/// let sum = lup!(Sum<f64>: i by xs -> {xs[i]});
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate lup;
+/// # use lup::Sum;
+/// # fn main() {
+/// # let xs = [1.0, 2.0];
+/// // error: lup!: expected `:` after the loop type
+/// let sum = lup!(Sum<f64> i by xs => {xs[i]});
+/// # }
+/// ```
+///
+/// Loops that take parameters are constructed with `<Loop>(<args>)` instead of a type,
+/// e.g. `lup!(Horner(x): i by coeffs => {coeffs[i]})`, which calls `Horner::new(x)`.
+/// Loops implementing `LupWith` are started with a context after the type,
+/// e.g. `lup!(Sum<f64> with 10.0: i by xs => {xs[i]})`, which calls `start_with(10.0)`.
+/// Both forms support single `in` and `by` indices,
+/// and two packed indices, which drive a single loop with `(i, j)` as index.
+///
+/// Two `in` ranges can be followed by `tiles (<height>, <width>)`,
+/// e.g. `i in 0..h, j in 0..w tiles (32, 32) =>`, to visit the indices tile by tile.
+/// This is a single loop with `(i, j)` as index,
+/// so evidence is the same as for the untiled loop,
+/// except that ties are resolved in tile order.
+/// The built-in loops that return evidence (`Any`, `All`, `Max` and `Min`)
+/// support up to four nested loops, with evidence `(usize, usize, usize, usize)`,
+/// using `usize`, `u32` or `u16` as index type.
+/// An outer loop over indices can also wrap an inner loop over keys implementing `Key`,
+/// e.g. `char` or `String`, with evidence `(usize, K)`.
 #[macro_export]
 macro_rules! lup(
+    (@tiles [$sum:ty : $i:tt in $iter:expr , $j:tt in] [$($iter2:tt)+]
+     tiles $tiles:tt => $body:block) => {{
+        let mut __lup_iter = $crate::Tiles::new($iter, $($iter2)+, $tiles);
+        let mut __lup_acc: $crate::Driver<$sum, _, _> = $crate::Driver::start_for(&__lup_iter);
+        while let Some(($i, $j)) = __lup_iter.next() {
+            let __lup_val = $body;
+            if !__lup_acc.it(($i, $j), __lup_val) {break};
+        }
+        __lup_acc.unwrap()
+    }};
+    (@tiles [$($head:tt)*] [$($iter2:tt)*] $t:tt $($rest:tt)*) => {
+        lup!(@tiles [$($head)*] [$($iter2)* $t] $($rest)*)
+    };
+    (@downto [$sum:ty : $i:tt] [$($from:tt)+] downto $to:expr => $body:block) => {
+        lup!($sum : $i in ($to..=$($from)+).rev() => $body)
+    };
+    (@downto [$($head:tt)*] [$($from:tt)*] $t:tt $($rest:tt)*) => {
+        lup!(@downto [$($head)*] [$($from)* $t] $($rest)*)
+    };
+    (@by [$($head:tt)*] [$($list:tt)+] skip $skip:tt take $take:tt => $body:block) => {{
+        let __lup_len = {use $crate::Len; ($($list)+).lup_len()};
+        let __lup_start = usize::min($skip, __lup_len);
+        let __lup_end = __lup_start + usize::min($take, __lup_len - __lup_start);
+        lup!($($head)* in __lup_start..__lup_end => $body)
+    }};
+    (@by [$($head:tt)*] [$($list:tt)+] skip $skip:tt => $body:block) => {
+        lup!(@by [$($head)*] [$($list)+] skip $skip take (usize::MAX) => $body)
+    };
+    (@by [$($head:tt)*] [$($list:tt)+] take $take:tt => $body:block) => {
+        lup!(@by [$($head)*] [$($list)+] skip 0 take $take => $body)
+    };
+    (@by [$sum:ty : $i:tt] [$($list:tt)+] offset $n:tt => $body:block) => {{
+        let __lup_offset: usize = $n;
+        let __lup_len = {use $crate::Len; ($($list)+).lup_len()};
+        lup!($sum : (__lup_abs, $i) in (0..__lup_len).map(|i| (i + __lup_offset, i)) => $body)
+    }};
+    (@by [$($head:tt)*] [$($list:tt)*] => $body:block) => {
+        compile_error!("lup!: expected `skip <n>`, `take <n>` or `offset <n>` after the list")
+    };
+    (@by [$($head:tt)*] [$($list:tt)*] $t:tt $($rest:tt)*) => {
+        lup!(@by [$($head)*] [$($list)* $t] $($rest)*)
+    };
+    (@with [$($head:tt)*] with $($name:pat = $val:expr),+ => $body:block) => {
+        lup!($($head)* => {$(let $name = $val;)+ $body})
+    };
+    (@with [$($head:tt)*] with $($name:pat = $val:expr),+ => $body:expr) => {
+        lup!($($head)* => {$(let $name = $val;)+ $body})
+    };
+    (@with [$($head:tt)*] => $body:block) => {
+        lup!(@ext $body $($head)*)
+    };
+    (@with [$($head:tt)*] => $body:expr) => {
+        lup!($($head)* => {$body})
+    };
+    (@with [$($head:tt)*] -> $($rest:tt)*) => {
+        compile_error!("lup!: expected `=>` before the body")
+    };
+    (@with [$($head:tt)*] $t:tt $($rest:tt)*) => {
+        lup!(@with [$($head)* $t] $($rest)*)
+    };
+    (@with [$($head:tt)*]) => {
+        compile_error!("lup!: expected `=> {<body>}` after the indices")
+    };
+    (@ext $body:block $sum:ty : $i:tt in $iter:expr , $j:tt in $($iter2:tt)+) => {
+        lup!(@tiles [$sum : $i in $iter , $j in] [] $($iter2)+ => $body)
+    };
+    (@ext $body:block $sum:ty : $i:tt in $($from:tt)+) => {
+        lup!(@downto [$sum : $i] [] $($from)+ => $body)
+    };
+    (@ext $body:block $sum:ty : $i:tt by $($rest:tt)+) => {
+        lup!(@by [$sum : $i] [] $($rest)+ => $body)
+    };
+    (@ext $body:block $($head:tt)*) => {
+        compile_error!("lup!: expected indices such as `i in <iterator>` or `i by <list>` before `=>`")
+    };
+    (@init [$driver:expr] : $i:tt by $list:expr => $body:block) => {
+        lup!(@init [$driver] : $i in 0..{use $crate::Len; $list.lup_len()} => $body)
+    };
+    (@init [$driver:expr] : $i:ident , $j:ident by $list:expr => $body:block) => {
+        lup!(@init [$driver] : $i in 0..{use $crate::Len; $list.lup_len()},
+                               $j in 0..{use $crate::Len; $list[$i].lup_len()} => $body)
+    };
+    (@init [$driver:expr] : $i:ident in $iter:expr , $j:ident in $iter2:expr
+     => $body:block) => {{
+        let mut __lup_acc = $driver;
+        '__lup_outer: for $i in $iter {
+            for $j in $iter2 {
+                let __lup_val = $body;
+                if !__lup_acc.it(($i, $j), __lup_val) {break '__lup_outer};
+            }
+        }
+        __lup_acc.unwrap()
+    }};
+    (@init [$driver:expr] : _ in $iter:expr => $body:block) => {
+        lup!(@init [$driver] : __lup_ind in $iter => $body)
+    };
+    (@init [$driver:expr] : $i:tt in $iter:expr => $body:block) => {{
+        let mut __lup_iter = $crate::into_iter($iter);
+        let mut __lup_acc = $driver;
+        while let Some($i) = __lup_iter.next() {
+            let __lup_val = $body;
+            if !__lup_acc.it($i, __lup_val) {break};
+        }
+        __lup_acc.unwrap()
+    }};
+    (@init [$driver:expr] : $($rest:tt)+) => {
+        lup!(@with [@init [$driver] :] $($rest)+)
+    };
+    (@ascribe [$t:ty] [$($head:tt)*] => $body:block) => {
+        lup!($($head)* => {let __lup_body: $t = $body; __lup_body})
+    };
+    (@ascribe [$t:ty] [$($head:tt)*] => $body:expr) => {
+        lup!($($head)* => {let __lup_body: $t = $body; __lup_body})
+    };
+    (@ascribe [$t:ty] [$($head:tt)*] $tt:tt $($rest:tt)*) => {
+        lup!(@ascribe [$t] [$($head)* $tt] $($rest)*)
+    };
+    (@ctx [$($ty:tt)+] with $($rest:tt)+) => {
+        lup!(@ctx_arg [$($ty)+] [] $($rest)+)
+    };
+    (@ctx [$($ty:tt)+] -> $t:ty : $($rest:tt)+) => {
+        lup!(@ascribe [$t] [$($ty)+ :] $($rest)+)
+    };
+    (@ctx [$($ty:tt)*] : $($rest:tt)*) => {
+        lup!(@colon $($ty)* : $($rest)*)
+    };
+    (@ctx [$($ty:tt)*] $t:tt $($rest:tt)*) => {
+        lup!(@ctx [$($ty)* $t] $($rest)*)
+    };
+    (@ctx [$($ty:tt)*]) => {
+        lup!(@colon $($ty)*)
+    };
+    (@ctx_arg [$($ty:tt)+] [$($ctx:tt)+] : $($rest:tt)+) => {
+        lup!(@init [$crate::Driver::<$($ty)+, _, _>::start_with($($ctx)+)] : $($rest)+)
+    };
+    (@ctx_arg [$($ty:tt)+] [$($ctx:tt)*] $t:tt $($rest:tt)*) => {
+        lup!(@ctx_arg [$($ty)+] [$($ctx)* $t] $($rest)*)
+    };
+    (@ctx_arg [$($ty:tt)+] [$($ctx:tt)*]) => {
+        lup!(@colon $($ty)+ with $($ctx)*)
+    };
+    ($name:ident ( $($args:tt)* ) : $($rest:tt)+) => {
+        lup!(@init [$crate::Driver::new($name::new($($args)*))] : $($rest)+)
+    };
+    (@colon) => {
+        compile_error!("lup!: expected `:` after the loop type")
+    };
+    (@colon : $($rest:tt)*) => {
+        compile_error!("lup!: expected `<loop type>: <indices> => {<body>}`")
+    };
+    (@colon $t:tt $($rest:tt)*) => {
+        lup!(@colon $($rest)*)
+    };
+    (@flow [$sum:ty] $i:tt , $($j:tt),+ by $list:expr => $body:block) => {
+        lup!(@flow [$sum] $i in 0..{use $crate::Len; $list.lup_len()} => {
+            lup!(@flow_up [$sum] $($j),+ by $list[$i] => $body)
+        })
+    };
+    (@flow [$sum:ty] $i:tt by $list:expr => $body:block) => {
+        lup!(@flow [$sum] $i in 0..{use $crate::Len; $list.lup_len()} => $body)
+    };
+    (@flow [$sum:ty] $i:tt in $iter:expr , $($j:tt in $iter2:expr),+ => $body:block) => {
+        lup!(@flow [$sum] $i in $iter => {
+            lup!(@flow_up [$sum] $($j in $iter2),+ => $body)
+        })
+    };
+    (@flow [$sum:ty] _ in $iter:expr => $body:block) => {
+        lup!(@flow [$sum] __lup_ind in $iter => $body)
+    };
+    (@flow [$sum:ty] $i:tt in $iter:expr => $body:block) => {{
+        let mut __lup_iter = $crate::into_iter($iter);
+        let mut __lup_acc: $crate::Driver<$sum, _, _> = $crate::Driver::start_for(&__lup_iter);
+        let mut __lup_broken = false;
+        while let Some($i) = __lup_iter.next() {
+            match $body {
+                $crate::Flow::Continue(__lup_val) => if !__lup_acc.it($i, __lup_val) {break},
+                $crate::Flow::Skip => {}
+                $crate::Flow::Break(__lup_val) => {
+                    __lup_acc.it($i, __lup_val);
+                    __lup_broken = true;
+                    break
+                }
+            }
+        }
+        (__lup_broken, __lup_acc.unwrap())
+    }};
+    (@flow_up [$sum:ty] $($rest:tt)+) => {
+        match lup!(@flow [$sum] $($rest)+) {
+            (true, __lup_inner) => $crate::Flow::Break(__lup_inner),
+            (false, __lup_inner) => $crate::Flow::Continue(__lup_inner),
+        }
+    };
+    ($sum:ty : $i:tt $(, $j:tt)* by $list:expr => flow $body:block) => {
+        lup!(@flow [$sum] $i $(, $j)* by $list => $body).1
+    };
+    ($sum:ty : $i:tt in $iter:expr $(, $j:tt in $iter2:expr)* => flow $body:block) => {
+        lup!(@flow [$sum] $i in $iter $(, $j in $iter2)* => $body).1
+    };
+    ($sum:ty : ($i:ident , $j:ident) by $list:expr => $body:block) => {
+        lup!($sum : __lup_pair in $crate::pairs({use $crate::Len; $list.lup_len()}) => {
+            let ($i, $j) = __lup_pair;
+            $body
+        })
+    };
     ($sum:ty : $i:tt , $($j:tt),+ by $list:expr => $body:block) => {
-        lup!($sum : $i in 0..$list.len() => {
+        lup!($sum : $i in 0..{use $crate::Len; $list.lup_len()} => {
             lup!($sum : $($j),* by $list[$i] => $body)
         })
     };
@@ -202,24 +741,366 @@ macro_rules! lup(
         })
     };
     ($sum:ty : $i:tt by $list:expr => $body:block) => {
-        lup!($sum : $i in 0..$list.len() => $body)
+        lup!($sum : $i in 0..{use $crate::Len; $list.lup_len()} => $body)
+    };
+    ($sum:ty : $i:tt : $idx:ident by $list:expr => $body:block) => {
+        lup!($sum : $i in 0..({use $crate::Len; $list.lup_len()} as $idx) => $body)
+    };
+    ($sum:ty : $i:tt , $($j:tt),+ : $idx:ident by $list:expr => $body:block) => {
+        lup!($sum : $i in 0..({use $crate::Len; $list.lup_len()} as $idx) => {
+            lup!($sum : $($j),* : $idx by $list[$i as usize] => $body)
+        })
+    };
+    ($sum:ty : $i:tt , $j:tt by2 $matrix:expr => $body:block) => {
+        lup!($sum : $i in 0..$crate::Shape2::rows(&$matrix),
+                    $j in 0..$crate::Shape2::cols(&$matrix) => $body)
+    };
+    ($sum:ty : $k:tt of $map:expr => $body:block) => {
+        lup!($sum : $k in $map.keys().cloned() => $body)
+    };
+    ($sum:ty : $w:tt window $n:tt by $list:expr => $body:block) => {{
+        let __lup_len: usize = $n;
+        let __lup_count = {use $crate::Len; $list.lup_len()}.saturating_sub(__lup_len.saturating_sub(1));
+        let __lup_starts = 0..__lup_count;
+        lup!($sum : (__lup_start, $w) in __lup_starts.map(|i| (i, i..i + __lup_len)) => $body)
+    }};
+    ($sum:ty : _ in $iter:expr => $body:block) => {
+        lup!($sum : __lup_ind in $iter => $body)
+    };
+    ($sum:ty : (_ , $v:pat) in $iter:expr => $body:block) => {
+        lup!($sum : (__lup_ind, $v) in $iter => $body)
+    };
+    ($sum:ty : ($i:tt , $v:pat) in $iter:expr => $body:block) => {{
+        let mut __lup_iter = $crate::into_iter($iter);
+        let mut __lup_acc: $crate::Driver<$sum, _, _> = $crate::Driver::start_for(&__lup_iter);
+        while let Some(($i, $v)) = __lup_iter.next() {
+            let __lup_val = $body;
+            if !__lup_acc.it($i, __lup_val) {break};
+        }
+        __lup_acc.unwrap()
+    }};
+    ($sum:ty : $i:ident in $start:tt .. $end:expr => $body:block) => {{
+        #[allow(unused_imports)]
+        use $crate::{OrderedMerge as _, UnorderedMerge as _};
+        let mut __lup_iter = $start..$end;
+        let mut __lup_acc: $crate::Driver<$sum, _, _> = $crate::Driver::start_for(&__lup_iter);
+        // Never runs, but fixes the types of the loop before choosing the expansion.
+        while let Some($i) = $crate::no_item(&__lup_iter) {
+            let __lup_val = $body;
+            __lup_acc.it($i, __lup_val);
+        }
+        match (&&__lup_acc).lup_merger() {
+            None => {
+                while let Some($i) = __lup_iter.next() {
+                    let __lup_val = $body;
+                    if !__lup_acc.it($i, __lup_val) {break};
+                }
+            }
+            Some(__lup_merge) => {
+                let mut __lup_lanes: [$crate::Driver<$sum, _, _>; 4] = [
+                    $crate::Driver::start_for(&__lup_iter),
+                    $crate::Driver::start_for(&__lup_iter),
+                    $crate::Driver::start_for(&__lup_iter),
+                    $crate::Driver::start_for(&__lup_iter),
+                ];
+                // Visits the items in chunks of four, passing the `k`-th item of a chunk
+                // to the `k`-th lane, so consecutive items do not depend on each other.
+                // A `break` in the body leaves the chunk early, which also ends the loop.
+                '__lup_outer: loop {
+                    let mut __lup_complete = false;
+                    for __lup_k in 0..5 {
+                        if __lup_k == 4 {__lup_complete = true; break}
+                        let $i = match __lup_iter.next() {
+                            Some(__lup_ind) => __lup_ind,
+                            None => break '__lup_outer,
+                        };
+                        let __lup_val = $body;
+                        if !__lup_lanes[__lup_k].it($i, __lup_val) {break '__lup_outer}
+                    }
+                    if !__lup_complete {break}
+                }
+                for __lup_lane in __lup_lanes {__lup_merge(&mut __lup_acc, __lup_lane)}
+            }
+        }
+        __lup_acc.unwrap()
+    }};
+    ($sum:ty : $i:tt in $iter:expr => $body:block) => {{
+        let mut __lup_iter = $crate::into_iter($iter);
+        let mut __lup_acc: $crate::Driver<$sum, _, _> = $crate::Driver::start_for(&__lup_iter);
+        while let Some($i) = __lup_iter.next() {
+            let __lup_val = $body;
+            if !__lup_acc.it($i, __lup_val) {break};
+        }
+        __lup_acc.unwrap()
+    }};
+    ($sum:ty : $($rest:tt)+) => {
+        lup!(@with [$sum :] $($rest)+)
+    };
+    ($($t:tt)*) => {
+        lup!(@ctx [] $($t)*)
+    }
+);
+
+/// Runs a custom loop with a fallible body.
+///
+/// Uses the same syntax as `lup!`, but the body returns a `Result`.
+/// `Ok` values are passed on to the loop,
+/// while the first `Err` stops the loop and is returned.
+/// The result of the whole loop is `Result<Inner, Err>`.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Sum;
+///
+/// fn main() {
+///     let words = vec!["1.5", "2", "x"];
+///     let sum = try_lup!(Sum<f32>: i by words => {words[i].parse::<f32>()});
+///     assert!(sum.is_err());
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_lup(
+    ($sum:ty : $i:tt , $($j:tt),+ by $list:expr => $body:block) => {
+        try_lup!($sum : $i in 0..{use $crate::Len; $list.lup_len()} => {
+            try_lup!($sum : $($j),* by $list[$i] => $body)
+        })
+    };
+    ($sum:ty : $i:tt in $iter:expr , $($j:tt in $iter2:expr),+ => $body:block) => {
+        try_lup!($sum : $i in $iter => {
+            try_lup!($sum : $($j in $iter2),* => $body)
+        })
+    };
+    ($sum:ty : $i:tt by $list:expr => $body:block) => {
+        try_lup!($sum : $i in 0..{use $crate::Len; $list.lup_len()} => $body)
+    };
+    ($sum:ty : $k:tt of $map:expr => $body:block) => {
+        try_lup!($sum : $k in $map.keys().cloned() => $body)
     };
+    ($sum:ty : _ in $iter:expr => $body:block) => {
+        try_lup!($sum : __lup_ind in $iter => $body)
+    };
+    ($sum:ty : (_ , $v:pat) in $iter:expr => $body:block) => {
+        try_lup!($sum : (__lup_ind, $v) in $iter => $body)
+    };
+    ($sum:ty : ($i:tt , $v:pat) in $iter:expr => $body:block) => {{
+        let mut __lup_iter = $crate::into_iter($iter);
+        let mut __lup_acc: $crate::Driver<$sum, _, _> = $crate::Driver::start_for(&__lup_iter);
+        let mut __lup_err = None;
+        while let Some(($i, $v)) = __lup_iter.next() {
+            match $body {
+                Ok(__lup_val) => if !__lup_acc.it($i, __lup_val) {break},
+                Err(e) => {
+                    __lup_err = Some(e);
+                    break;
+                }
+            }
+        }
+        match __lup_err {
+            None => Ok(__lup_acc.unwrap()),
+            Some(e) => Err(e),
+        }
+    }};
     ($sum:ty : $i:tt in $iter:expr => $body:block) => {{
-        use $crate::Lup;
-        let mut sum: $sum = Lup::start();
-        let mut iter = $iter;
-        while let Some($i) = iter.next() {
-            if !sum.it($i, $body) {break};
+        let mut __lup_iter = $crate::into_iter($iter);
+        let mut __lup_acc: $crate::Driver<$sum, _, _> = $crate::Driver::start_for(&__lup_iter);
+        let mut __lup_err = None;
+        while let Some($i) = __lup_iter.next() {
+            match $body {
+                Ok(__lup_val) => if !__lup_acc.it($i, __lup_val) {break},
+                Err(e) => {
+                    __lup_err = Some(e);
+                    break;
+                }
+            }
+        }
+        match __lup_err {
+            None => Ok(__lup_acc.unwrap()),
+            Some(e) => Err(e),
         }
-        sum.unwrap()
     }}
 );
 
-#[cfg(test)]
+/// Runs a loop in a const context.
+///
+/// Trait methods can not be called in a const context,
+/// so this macro expands to a plain `while` loop instead of using the `Lup` trait.
+/// It supports `Sum` and `Vector` over integer and float types,
+/// with the `in` and `by` syntax for a single index.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate lup;
+///
+/// const TABLE: [f32; 8] = const_lup!(Vector<[f32; 8]>: i in 0..8 => {i as f32 * 0.5});
+/// const TOTAL: f32 = const_lup!(Sum<f32>: i by TABLE => {TABLE[i]});
+///
+/// fn main() {
+///     println!("{}", TOTAL); // Prints `14`.
+/// }
+/// ```
+#[macro_export]
+macro_rules! const_lup(
+    ($sum:ident<$t:ty> : $i:ident by $list:expr => $body:block) => {
+        const_lup!($sum<$t> : $i in 0..$list.len() => $body)
+    };
+    (Sum<$t:ty> : $i:ident in $iter:expr => $body:block) => {{
+        let __lup_range = $iter;
+        let mut __lup_acc = 0 as $t;
+        let mut __lup_ind = __lup_range.start;
+        while __lup_ind < __lup_range.end {
+            let $i = __lup_ind;
+            __lup_ind += 1;
+            __lup_acc += $body;
+        }
+        __lup_acc
+    }};
+    (Vector<[$t:ty; $n:expr]> : $i:ident in $iter:expr => $body:block) => {{
+        let __lup_range = $iter;
+        let mut __lup_acc = [0 as $t; $n];
+        let mut __lup_ind = __lup_range.start;
+        while __lup_ind < __lup_range.end {
+            let $i = __lup_ind;
+            __lup_ind += 1;
+            __lup_acc[$i] = $body;
+        }
+        __lup_acc
+    }};
+);
+
+/// Proves a statement by brute force.
+///
+/// The statement is one or more quantified clauses,
+/// `forall <index> in <iterator>` or `exists <index> in <iterator>`,
+/// separated by commas and followed by the body.
+/// The clauses are run as nested `Forall` and `Exists` loops.
+/// The body can be preceded by a hypothesis, `=> {<hypothesis>} => {<conclusion>}`,
+/// which makes the body the implication from hypothesis to conclusion.
+///
+/// Returns `Ok(())` if the statement holds,
+/// or `Err(Counterexample)` with the evidence of the outermost loop if it does not.
+/// The counterexample also records the values of the leading `forall` variables
+/// where the statement fails, as a tuple in the order of the clauses.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate lup;
+///
+/// fn main() {
+///     let res = prove!(forall a in 0..10usize, forall b in 0..10usize => {a + b == b + a});
+///     assert!(res.is_ok());
+///
+///     let res = prove!(forall a in 0..10u32, forall b in 0..10u32 => {a < b} => {a * a < b * b});
+///     assert!(res.is_ok());
+///
+///     let res = prove!(forall n in 0..100 => {n * n < 50});
+///     // Prints `Err(Counterexample { evidence: Some(8), values: Some((8,)) })`.
+///     println!("{:?}", res);
+/// }
+/// ```
+#[macro_export]
+macro_rules! prove(
+    (@ty forall) => {$crate::Forall<_>};
+    (@ty exists) => {$crate::Exists<_>};
+    (@ty $($t:tt)*) => {
+        compile_error!("prove!: expected `forall <index> in <iterator>` or `exists <index> in <iterator>`")
+    };
+    (@test $w:ident ($($v:tt)*) => $hyp:block => $body:block) => {
+        prove!(@test $w ($($v)*) => {!$hyp || $body})
+    };
+    (@test $w:ident ($($v:tt)*) => $body:block) => {{
+        let __lup_holds: bool = $body;
+        if !__lup_holds {$w = Some(($(Clone::clone(&$v),)*));}
+        __lup_holds
+    }};
+    (@lup $w:ident ($($v:tt)*) forall $i:tt in $iter:expr => $($tail:tt)+) => {
+        lup!($crate::Forall<_>: $i in $iter => {prove!(@test $w ($($v)* $i) => $($tail)+)})
+    };
+    (@lup $w:ident ($($v:tt)*) forall $i:tt in $iter:expr , $($rest:tt)+) => {
+        lup!($crate::Forall<_>: $i in $iter => {prove!(@lup $w ($($v)* $i) $($rest)+)})
+    };
+    (@lup $w:ident $v:tt exists $($rest:tt)+) => {
+        prove!(@rest $w $v exists $($rest)+)
+    };
+    (@rest $w:ident $v:tt $q:ident $i:tt in $iter:expr => $($tail:tt)+) => {
+        lup!(prove!(@ty $q): $i in $iter => {prove!(@test $w $v => $($tail)+)})
+    };
+    (@rest $w:ident $v:tt $q:ident $i:tt in $iter:expr , $($rest:tt)+) => {
+        lup!(prove!(@ty $q): $i in $iter => {prove!(@rest $w $v $($rest)+)})
+    };
+    (@$tag:ident $($rest:tt)*) => {
+        compile_error!("prove!: expected `forall <index> in <iterator>` or `exists <index> in <iterator>`")
+    };
+    ($($t:tt)+) => {{
+        let mut __lup_values = None;
+        let __lup_res = prove!(@lup __lup_values () $($t)+);
+        if __lup_res.value {
+            Ok(())
+        } else {
+            Err($crate::Counterexample {evidence: __lup_res.evidence, values: __lup_values})
+        }
+    }};
+);
+
+/// Asserts that a secret is true, printing the evidence on failure.
+///
+/// Takes either a `Secret<E, bool>` or a loop invocation using the `lup!` syntax.
+/// When the secret is false, the panic message contains the evidence,
+/// e.g. the counterexample of an `All` loop,
+/// or says that no witness was found, e.g. for an `Any` loop.
+///
+/// A list can be passed as second argument to also print the item at the evidence index.
+///
+/// ```rust,should_panic
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::All;
+///
+/// fn main() {
+///     let xs = [1, 2, -3];
+///     // Panics with `assert_lup! failed: counterexample at 2: -3`.
+///     assert_lup!(lup!(All<_>: i by xs => {xs[i] > 0}), xs);
+/// }
+/// ```
+#[macro_export]
+macro_rules! assert_lup(
+    ($sum:ty : $($rest:tt)+) => {
+        assert_lup!(lup!($sum : $($rest)+))
+    };
+    ($secret:expr) => {{
+        let __lup_res = $secret;
+        if !__lup_res.value {
+            $crate::assert_lup_failed(__lup_res.evidence.as_ref(), None::<&()>)
+        }
+    }};
+    ($secret:expr , $list:expr) => {{
+        let __lup_res = $secret;
+        if !__lup_res.value {
+            match __lup_res.evidence {
+                Some(e) => $crate::assert_lup_failed(Some(&e), Some(&$list[e])),
+                None => $crate::assert_lup_failed(None::<&()>, None::<&()>),
+            }
+        }
+    }};
+);
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use std::prelude::v1::*;
+    #[cfg(feature = "proptest")]
+    use proptest::strategy::Strategy;
+
+    /// Pseudo-random numbers in `[0, 1)` from a linear congruential generator.
+    fn lcg(seed: &mut u64) -> f64 {
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (*seed >> 11) as f64 / (1u64 << 53) as f64
+    }
 
     #[test]
+    #[allow(clippy::useless_vec, clippy::bool_assert_comparison)]
     fn list() {
         type Num = f32;
         let list = vec![vec![vec![1.0, 2.0, 3.0]]];
@@ -303,6 +1184,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec, clippy::bool_assert_comparison)]
     fn vector() {
         let list = vec![[0.2, 0.3, 0.4], [0.1, 0.5, 0.7]];
 
@@ -317,6 +1199,23 @@ mod tests {
     }
 
     #[test]
+    fn vector_from() {
+        let list = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let v = lup!(VectorFrom<[f32; 3]>: i in 2..5 => {list[i]});
+        assert_eq!(v, [0.3, 0.4, 0.5]);
+
+        let v = lup!(VectorFrom<[f32; 4]>: i in 1..3 => {list[i]});
+        assert_eq!(v, [0.2, 0.3, 0.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 5 is out of bounds for VectorFrom of length 3 starting at index 2")]
+    fn vector_from_overflow() {
+        lup!(VectorFrom<[f32; 3]>: i in 2..6 => {i as f32});
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn sift() {
         let list = lup!(Sift<Vec<f32>>: i in 0..5 => {i as f32 + 1.0});
         assert_eq!(list, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
@@ -341,19 +1240,2345 @@ mod tests {
     }
 
     #[test]
-    fn short() {
-        let list = lup!(Sift<_>: i in 0..3, j in 0..3, k in 0..3 => {
-            i as f32 + j as f32 + k as f32
+    fn link() {
+        let text = lup!(Link: i in 0..3 => {i});
+        assert_eq!(text, "012");
+
+        let items = ["x", "y"];
+        let text = lup!(Link: i by items => {
+            if i == 0 {lup!(Link: _j in 0..2 => {'-'})} else {format!("{}={}", items[i], i * 10)}
         });
-        let a = lup!(Sum<_>: i, j, k by list => {list[i][j][k]});
-        assert_eq!(a, 81.0);
+        assert_eq!(text, "--y=10");
+
+        // Mixing numbers, strings and characters in the same loop.
+        let mut link = <Link as Lup<usize, i32>>::start();
+        link.it(0, 42);
+        link.it(1, ", ");
+        link.it(2, 'c');
+        link.it(3, 1.5);
+        assert_eq!(link.0, "42, c1.5");
+
+        let empty = lup!(Link: i in 0..0 => {i});
+        assert_eq!(empty, "");
     }
 
     #[test]
-    fn for_loop() {
-        let list = vec![vec![1, 2], vec![3, 4]];
-        lup!(For: i, j by list => {
-            println!("{}", list[i][j]);
+    fn small_index() {
+        let n = 5u32;
+        let any = lup!(Any<u32>: i in 0u32..n => {i * i == 9});
+        let ev: Option<u32> = any.evidence;
+        assert_eq!(ev, Some(3));
+
+        let data = [[1.0, 5.0, 2.0], [7.0, 3.0, 0.0]];
+        let max = lup!(Max<_, f64>: i, j: u32 by data => {data[i as usize][j as usize]});
+        let ev: Option<(u32, u32)> = max.evidence;
+        assert_eq!(ev, Some((1, 0)));
+
+        let min = lup!(Min<_, f64>: i, j: u16 by data => {data[i as usize][j as usize]});
+        let ev: Option<(u16, u16)> = min.evidence;
+        assert_eq!(ev, Some((1, 2)));
+
+        let all = lup!(All<_>: i, j: u16 by data => {data[i as usize][j as usize] > 0.5});
+        let ev: Option<(u16, u16)> = all.evidence;
+        assert_eq!(ev, Some((1, 2)));
+
+        let cube = [[[false, false], [false, true]], [[false; 2]; 2]];
+        let any = lup!(Any<_>: i, j, k: u16 by cube => {cube[i as usize][j as usize][k as usize]});
+        let ev: Option<(u16, u16, u16)> = any.evidence;
+        assert_eq!(ev, Some((0, 1, 1)));
+
+        let max = lup!(Max<_, f32>: i: u32 by data => {data[i as usize][0] as f32});
+        assert_eq!(max.evidence, Some(1u32));
+    }
+
+    #[test]
+    fn mixed_evidence() {
+        use std::collections::BTreeMap;
+
+        let rows: Vec<BTreeMap<char, u32>> = vec![
+            [('a', 1), ('b', 2)].iter().cloned().collect(),
+            [('c', 3), ('d', 8)].iter().cloned().collect(),
+        ];
+        let any = lup!(Any<_>: i by rows => {
+            lup!(Any<_>: k of rows[i] => {rows[i][&k] > 5})
+        });
+        let ev: Option<(usize, char)> = any.evidence;
+        assert_eq!(ev, Some((1, 'd')));
+
+        let all = lup!(All<_>: i by rows => {
+            lup!(All<_>: k of rows[i] => {rows[i][&k] < 3})
+        });
+        assert_eq!(all.evidence, Some((1, 'c')));
+
+        let names: Vec<BTreeMap<String, f64>> = vec![
+            [("x".to_string(), 1.0), ("y".to_string(), 4.0)].iter().cloned().collect(),
+            [("z".to_string(), -1.0)].iter().cloned().collect(),
+        ];
+        let max = lup!(Max<_, _>: i by names => {
+            lup!(Max<_, _>: k of names[i] => {names[i][&k]})
+        });
+        assert_eq!(max.evidence, Some((0, "y".to_string())));
+        let min = lup!(Min<_, _>: i by names => {
+            lup!(Min<_, _>: k of names[i] => {names[i][&k]})
+        });
+        assert_eq!(min.evidence, Some((1, "z".to_string())));
+    }
+
+    #[test]
+    fn start_sized() {
+        let list = lup!(Sift<Vec<f32>>: i in 0..100 => {i as f32});
+        assert_eq!(list.capacity(), 100);
+        let words = ["a", "b", "c"];
+        let list = lup!(Sift<Vec<&str>>: i by words => {words[i]});
+        assert_eq!(list.capacity(), 3);
+        let list = lup!(Sift<Vec<usize>>: i in (0..10).filter(|i| i % 2 == 0) => {i});
+        assert_eq!(list, vec![0, 2, 4, 6, 8]);
+        let list = run::<_, _, Sift<Vec<usize>>>(0..7, |i| i);
+        assert_eq!(list.capacity(), 7);
+
+        // Custom loops without `start_sized` still work.
+        struct Count(usize);
+        impl Lup<usize, ()> for Count {
+            type Inner = usize;
+            fn start() -> Count {Count(0)}
+            fn it(&mut self, _: usize, _: ()) -> bool {self.0 += 1; true}
+            fn unwrap(self) -> usize {self.0}
+        }
+        assert_eq!(lup!(Count: _i in 0..5 => {}), 5);
+    }
+
+    #[test]
+    fn finish() {
+        // Like `Any`, but also reports the number of iterations.
+        struct Search(Option<usize>);
+        impl Lup<usize, bool> for Search {
+            type Inner = (Option<usize>, usize);
+            fn start() -> Search {Search(None)}
+            fn it(&mut self, ind: usize, val: bool) -> bool {
+                if val {self.0 = Some(ind)}
+                !val
+            }
+            fn unwrap(self) -> (Option<usize>, usize) {(self.0, 0)}
+            fn finish(self, iterations: usize) -> (Option<usize>, usize) {(self.0, iterations)}
+        }
+
+        let list = [1, 5, 3, 8, 2];
+        assert_eq!(lup!(Search: i by list => {list[i] > 4}), (Some(1), 2));
+        assert_eq!(lup!(Search: i by list => {list[i] > 7}), (Some(3), 4));
+        assert_eq!(lup!(Search: i by list => {list[i] > 9}), (None, 5));
+        assert_eq!(lup!(Search: i in 0..0 => {i > 0}), (None, 0));
+        assert_eq!(run::<_, _, Search>(0..10, |i| i == 6), (Some(6), 7));
+        assert_eq!([false, true].iter().cloned().lup::<Search>(), (Some(1), 2));
+
+        let any = lup!(Any<_>: i by list => {list[i] > 4});
+        assert_eq!(any.evidence, Some(1));
+    }
+
+    #[test]
+    fn derives() {
+        // Custom loop that sums positive and negative values separately.
+        #[derive(Debug, Clone, Default)]
+        struct Split(Sum<f64>, Sum<f64>);
+        impl Lup<usize, f64> for Split {
+            type Inner = (f64, f64);
+            fn start() -> Split {Split::default()}
+            fn it(&mut self, _: usize, val: f64) -> bool {
+                if val >= 0.0 {(self.0).0 += val} else {(self.1).0 += val}
+                true
+            }
+            fn unwrap(self) -> (f64, f64) {((self.0).0, (self.1).0)}
+        }
+
+        let list = [1.0, -2.0, 3.5];
+        let mut split = Split::start();
+        for (i, &x) in list.iter().enumerate().take(2) {split.it(i, x);}
+        assert_eq!(format!("{:?}", split.clone()), "Split(Sum(1.0), Sum(-2.0))");
+        assert_eq!(lup!(Split: i by list => {list[i]}), (4.5, -2.0));
+
+        let max: Max<usize, f64> = Max(Secret {evidence: Some(2), value: 3.0});
+        assert_eq!(format!("{:?}", max.clone()), "Max(Secret { evidence: Some(2), value: 3.0 })");
+        assert_eq!(Any::<usize>::default().0.evidence, None);
+        assert_eq!(Sift::<Vec<u8>>::default().0, vec![]);
+    }
+
+    #[test]
+    fn all_with() {
+        // Check that `x` solves `a * x = b` for every row.
+        let rows: [(f64, f64, f64); 4] = [
+            (2.0, 3.0, 1.5),
+            (1.0, 1.0, 1.0),
+            (4.0, 2.0, 0.75),
+            (3.0, 3.0, 0.0),
+        ];
+        let res = lup!(AllWith<_, f64>: i by rows => {
+            let (a, b, x) = rows[i];
+            let residual = a * x - b;
+            (residual.abs() < 1e-9, residual)
+        });
+        assert_eq!(res.evidence, Some(2));
+        assert_eq!(res.value, Some(1.0));
+
+        let res = lup!(AllWith<_, f64>: i by rows[..2] => {(true, rows[i].0)});
+        assert_eq!(res.evidence, None);
+        assert_eq!(res.value, None);
+
+        let grid = [[0.1, 0.2], [0.3, 0.9]];
+        let res = lup!(AllWith<_, _>: i, j by grid => {
+            let err = grid[i][j] * 2.0;
+            (err < 1.0, format!("{}", err))
+        });
+        assert_eq!(res.evidence, Some((1, 1)));
+        assert_eq!(res.value, Some("1.8".to_string()));
+
+        let cube = [[[0; 2]; 2], [[0, 0], [0, 7]]];
+        let res = lup!(AllWith<_, i32>: i, j, k by cube => {(cube[i][j][k] == 0, cube[i][j][k])});
+        assert_eq!(res.evidence, Some((1, 1, 1)));
+        assert_eq!(res.value, Some(7));
+    }
+
+    #[test]
+    fn max_item() {
+        let res = {
+            let words: Vec<String> = "the quick brown fox jumps over the lazy dog"
+                .split(' ').map(|w| w.to_string()).collect();
+            lup!(MaxItem<_, String>: i by words => {(words[i].len() as f64, words[i].clone())})
+        };
+        assert_eq!(res.evidence, Some(1));
+        assert_eq!(res.value, Some((5.0, "quick".to_string())));
+
+        let empty: Vec<String> = vec![];
+        let res = lup!(MaxItem<_, String>: i by empty => {(0.0, empty[i].clone())});
+        assert_eq!(res.evidence, None);
+        assert_eq!(res.value, None);
+    }
+
+    #[test]
+    fn until() {
+        let errors = [0.5, 0.1, 0.01, 0.001, 0.0001];
+        let res = lup!(Until<_>: i by errors => {errors[i] < 1.0});
+        assert_eq!((res.value, res.evidence), (1, Some(0)));
+
+        let res = lup!(Until<_>: i by errors => {errors[i] < 1e-9});
+        assert_eq!((res.value, res.evidence), (5, None));
+
+        let res = lup!(Until<_>: i by errors => {errors[i] < 0.005});
+        assert_eq!((res.value, res.evidence), (4, Some(3)));
+    }
+
+    #[test]
+    fn prove() {
+        let res = prove!(forall a in 0..20usize, forall b in 0..20usize => {
+            (a + b) * (a + b) == a * a + 2 * a * b + b * b
+        });
+        assert_eq!(res, Ok(()));
+
+        fn product_is_not(a: usize, b: usize, n: usize) -> bool {a * b != n}
+        let res = prove!(forall a in 1..20, forall b in 1..20 => {product_is_not(a, b, 12)});
+        assert_eq!(res, Err(Counterexample {evidence: Some((1, 12)), values: Some((1, 12))}));
+
+        let res = prove!(forall n in 0..100 => {n * n < 50});
+        assert_eq!(res, Err(Counterexample {evidence: Some(8), values: Some((8,))}));
+
+        let res = prove!(exists n in 0..10 => {n * n == 49});
+        assert_eq!(res, Ok(()));
+
+        let res = prove!(exists n in 0..10 => {n * n == 50});
+        assert_eq!(res, Err(Counterexample {evidence: None, values: Some(())}));
+
+        let res = prove!(forall a in 0..20u32, forall b in 0..20u32 => {a < b} => {a * a < b * b});
+        assert_eq!(res, Ok(()));
+        let res = prove!(forall a in 0..20usize, forall b in 0..20usize => {a % 2 == 0 && b % 2 == 0} => {
+            (a + b) % 4 == 0
+        });
+        assert_eq!(res, Err(Counterexample {evidence: Some((0, 2)), values: Some((0, 2))}));
+
+        let res = prove!(forall a in 0..5usize, exists b in 0..5usize => {a + b == 5});
+        assert_eq!(res.map_err(|c| c.values), Err(Some((0,))));
+        let res = prove!(exists b in 0..5usize, forall a in 0..5usize => {a + b < 5});
+        assert_eq!(res.map_err(|c| c.values), Ok(()));
+
+        let words = ["a", "bb", "ccc"];
+        let res = prove!(forall w in words.iter() => {w.len() < 3});
+        assert_eq!(res, Err(Counterexample {evidence: Some(&"ccc"), values: Some((&"ccc",))}));
+
+        let found: Secret<usize, bool> = lup!(Exists<_>: i in 0..5 => {i == 3});
+        assert_eq!(found.evidence, Some(3));
+        let all: Secret<usize, bool> = lup!(Forall<_>: i in 0..5 => {i < 3});
+        assert_eq!(all.evidence, Some(3));
+    }
+
+    #[test]
+    fn assert_lup() {
+        let xs = [1, 2, 3];
+        assert_lup!(lup!(All<_>: i by xs => {xs[i] > 0}));
+        assert_lup!(Any<_>: i by xs => {xs[i] == 2});
+        assert_lup!(lup!(Any<_>: i by xs => {xs[i] == 3}), xs);
+    }
+
+    #[test]
+    #[should_panic(expected = "assert_lup! failed: counterexample at (1, 0)")]
+    fn assert_lup_all() {
+        let grid = [[1, 2], [-3, 4]];
+        assert_lup!(All<_>: i, j by grid => {grid[i][j] > 0});
+    }
+
+    #[test]
+    #[should_panic(expected = "assert_lup! failed: counterexample at 2: \"c\"")]
+    fn assert_lup_list() {
+        let words = ["a", "b", "c"];
+        let res = lup!(All<_>: i by words => {words[i] < "c"});
+        assert_lup!(res, words);
+    }
+
+    #[test]
+    #[should_panic(expected = "assert_lup! failed: no witness found")]
+    fn assert_lup_any() {
+        let xs = [1, 2, 3];
+        assert_lup!(Any<_>: i by xs => {xs[i] > 3});
+    }
+
+    #[test]
+    fn any_without_evidence() {
+        let found: Secret<usize, bool> = Secret {evidence: None, value: true};
+        let nothing: Secret<usize, bool> = Secret {evidence: None, value: false};
+
+        let res = lup!(Any<_>: i in 0..3usize => {if i == 1 {found.clone()} else {nothing.clone()}});
+        assert!(res.value);
+        assert_eq!(res.evidence, None);
+
+        let res = lup!(Any<_>: i in 0..3usize => {nothing.clone()});
+        assert!(!res.value);
+
+        let found: Secret<(usize, usize), bool> = Secret {evidence: None, value: true};
+        let nothing: Secret<(usize, usize), bool> = Secret {evidence: None, value: false};
+
+        let res = lup!(Any<_>: i in 0..3usize => {if i == 2 {found.clone()} else {nothing.clone()}});
+        assert!(res.value);
+        assert_eq!(res.evidence, None);
+
+        let res = lup!(Any<_>: i in 0..3usize => {
+            if i == 2 {Secret {evidence: Some((4, 5)), value: true}} else {nothing.clone()}
         });
+        assert_eq!(res.evidence, Some((2, 4, 5)));
+    }
+
+    #[test]
+    fn all_without_evidence() {
+        let failed: Secret<usize, bool> = Secret {evidence: None, value: false};
+        let passed: Secret<usize, bool> = Secret {evidence: None, value: true};
+
+        let res = lup!(All<_>: i in 0..3usize => {if i == 1 {failed.clone()} else {passed.clone()}});
+        assert!(!res.value);
+        assert_eq!(res.evidence, None);
+
+        let res = lup!(All<_>: _i in 0..3usize => {passed.clone()});
+        assert!(res.value);
+
+        let failed: Secret<(usize, usize), bool> = Secret {evidence: None, value: false};
+        let passed: Secret<(usize, usize), bool> = Secret {evidence: None, value: true};
+
+        let res = lup!(All<_>: i in 0..3usize => {if i == 2 {failed.clone()} else {passed.clone()}});
+        assert!(!res.value);
+        assert_eq!(res.evidence, None);
+
+        let res = lup!(All<_>: i in 0..3usize => {
+            if i == 1 {Secret {evidence: Some((0, 3)), value: false}} else {passed.clone()}
+        });
+        assert_eq!(res.evidence, Some((1, 0, 3)));
+    }
+
+    #[test]
+    fn max_min_without_evidence() {
+        let rows = [[1.0, 5.0], [2.0, 3.0], [4.0, 0.0]];
+        let max = lup!(Max<_, f64>: i by rows => {
+            if i == 1 {
+                Secret {evidence: None, value: 100.0}
+            } else {
+                lup!(Max<_, f64>: j by rows[i] => {rows[i][j]})
+            }
+        });
+        assert_eq!(max.value, 100.0);
+        assert_eq!(max.evidence, None);
+
+        let min = lup!(Min<_, f64>: i by rows => {
+            if i == 1 {
+                Secret {evidence: None, value: -100.0}
+            } else {
+                lup!(Min<_, f64>: j by rows[i] => {rows[i][j]})
+            }
+        });
+        assert_eq!(min.value, -100.0);
+        assert_eq!(min.evidence, None);
+
+        // A better value with evidence later on restores the evidence.
+        let max = lup!(Max<_, f64>: i in 0..3usize => {
+            if i == 0 {
+                Secret {evidence: None, value: 1.0}
+            } else {
+                Secret {evidence: Some((i, 7)), value: i as f64 * 10.0}
+            }
+        });
+        assert_eq!(max.value, 20.0);
+        assert_eq!(max.evidence, Some((2, 2, 7)));
+
+        let empty: [[f64; 0]; 2] = [[], []];
+        let max = lup!(Max<_, f64>: i, j by empty => {empty[i][j]});
+        assert!(max.value.is_nan());
+        assert_eq!(max.evidence, None);
+    }
+
+    #[test]
+    fn into_iter() {
+        let v: Vec<usize> = vec![3, 1, 4, 1, 5];
+        let sum = lup!(Sum<f64>: x in &v[..] => {*x as f64});
+        assert_eq!(sum, 14.0);
+        let any = lup!(Any<_>: x in &v => {*x == 4});
+        assert_eq!(any.evidence, Some(&4));
+        let list = lup!(Sift<Vec<usize>>: x in v => {x * 2});
+        assert_eq!(list, vec![6, 2, 8, 2, 10]);
+
+        let set: std::collections::BTreeSet<usize> = [5, 2, 9].iter().cloned().collect();
+        let max = lup!(Max<_, f64>: x in set => {x as f64});
+        assert_eq!(max.evidence, Some(9));
+
+        let pairs = vec![(0, 1.0), (1, -2.0)];
+        let sum = lup!(Sum<f64>: (_, x) in pairs => {x});
+        assert_eq!(sum, -1.0);
+        let res: Result<f64, ()> = try_lup!(Sum<f64>: x in vec![1.0, 2.0] => {Ok(x)});
+        assert_eq!(res, Ok(3.0));
+    }
+
+    #[test]
+    fn sift_indexed() {
+        let table = lup!(SiftIndexed<Vec<usize>>: i in (1..8).step_by(3) => {i * 10});
+        assert_eq!(table, vec![0, 10, 0, 0, 40, 0, 0, 70]);
+
+        let order = [4, 0, 2];
+        let table = lup!(SiftIndexed<Vec<&str>>: (i, w) in order.iter().cloned().zip(["e", "a", "c"]) => {w});
+        assert_eq!(table, vec!["a", "", "c", "", "e"]);
+
+        let n = 3;
+        let table = lup!(SiftIndexed<Vec<f32>>: i in n downto 0 => {i as f32});
+        assert_eq!(table, vec![0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn all_checked() {
+        let xs: [u32; 0] = [];
+        let res = lup!(AllChecked<_>: i by xs => {xs[i] > 0});
+        assert!(res.secret.value);
+        assert!(res.was_vacuous());
+
+        let xs = [1, 2, 3];
+        let res = lup!(AllChecked<_>: i by xs => {xs[i] > 0});
+        assert!(res.secret.value);
+        assert!(!res.was_vacuous());
+        assert_eq!(res.iterations, 3);
+
+        let res = lup!(AllChecked<_>: i by xs => {xs[i] < 2});
+        assert!(!res.secret.value);
+        assert_eq!(res.secret.evidence, Some(1));
+        assert_eq!(res.iterations, 2);
+    }
+
+    #[test]
+    fn strict() {
+        let clean = [2.0, 7.0, -1.0];
+        let max = lup!(MaxStrict<_, f64>: i by clean => {clean[i]}).unwrap();
+        assert_eq!((max.value, max.evidence), (7.0, Some(1)));
+        let min = lup!(MinStrict<_, f64>: i by clean => {clean[i]}).unwrap();
+        assert_eq!((min.value, min.evidence), (-1.0, Some(2)));
+
+        let first = [f32::NAN, 1.0];
+        let max = lup!(MaxStrict<_, f32>: i by first => {first[i]});
+        assert_eq!(max.unwrap_err(), NanAt(0));
+
+        let rows = [vec![1.0, 2.0], vec![3.0, f64::NAN, 9.0], vec![10.0]];
+        let max = lup!(MaxStrict<_, f64>: i, j by rows => {rows[i][j]});
+        assert_eq!(max.unwrap_err(), NanAt((1, 1)));
+        let min = lup!(MinStrict<_, f64>: i, j by rows[..1] => {rows[i][j]}).unwrap();
+        assert_eq!(min.evidence, Some((0, 0)));
+
+        let cube = [[[1.0, 2.0], [3.0, 0.5]], [[4.0, 1.5], [2.5, 3.5]]];
+        let max = lup!(MaxStrict<_, f64>: i, j, k by cube => {cube[i][j][k]}).unwrap();
+        assert_eq!(max.evidence, Some((1, 0, 0)));
+    }
+
+    #[test]
+    fn sum_wide() {
+        let n = 1_000_000;
+        let small = 1e-8f32;
+        let body = |i: usize| if i == 0 {1.0} else {small};
+        let narrow = lup!(Sum<f32>: i in 0..n => {body(i)});
+        let wide = lup!(SumWide<_>: i in 0..n => {body(i)});
+        let reference = 1.0 + (n - 1) as f64 * small as f64;
+        assert_eq!(narrow, 1.0);
+        assert!((wide - reference).abs() < 1e-9);
+
+        let wide = lup!(SumWide<_>: i in 0..n => {[body(i), 2.0 * body(i)]});
+        assert!((wide[0] - reference).abs() < 1e-9);
+        assert!((wide[1] - 2.0 * reference).abs() < 1e-9);
+        let wide: [f64; 4] = lup!(SumWide<_>: _i in 0..3 => {[0.5f32; 4]});
+        assert_eq!(wide, [1.5; 4]);
+    }
+
+    #[test]
+    fn prod_checked() {
+        let factors = [1e100, 1e100, 1e100, 1e100, 1e-100];
+        let prod = lup!(ProdChecked<f64>: i by factors => {factors[i]});
+        assert_eq!(prod.evidence, Some(3));
+        assert!(prod.value.is_infinite());
+
+        let factors = [2.0f32, 0.5, 3.0];
+        let prod = lup!(ProdChecked<f32>: i by factors => {factors[i]});
+        assert_eq!(prod.evidence, None);
+        assert_eq!(prod.value, 3.0);
+
+        let factors = [2.0, f64::NAN, 1.0];
+        let prod = lup!(ProdChecked<f64>: i by factors => {factors[i]});
+        assert_eq!(prod.evidence, Some(1));
+        assert!(prod.value.is_nan());
+    }
+
+    #[test]
+    fn covariance() {
+        let empty: [f64; 0] = [];
+        let cov = lup!(Covariance: i by empty => {(empty[i], empty[i])});
+        assert!(cov.is_nan());
+        assert_eq!(lup!(Covariance: _i in 0..1 => {(3.0, 4.0)}), 0.0);
+
+        // Pseudo-random samples.
+        let mut seed = 12345u64;
+        let mut rand = || lcg(&mut seed);
+        let xs: Vec<f64> = (0..1000).map(|_| rand() * 10.0 + 100.0).collect();
+        let ys: Vec<f64> = xs.iter().map(|x| x * 0.5 + rand()).collect();
+
+        let cov = lup!(Covariance: i by xs => {(xs[i], ys[i])});
+        let n = xs.len() as f64;
+        let mean_x = xs.iter().sum::<f64>() / n;
+        let mean_y = ys.iter().sum::<f64>() / n;
+        let reference = lup!(Sum<f64>: i by xs => {(xs[i] - mean_x) * (ys[i] - mean_y)}) / n;
+        assert!((cov - reference).abs() < 1e-9);
+    }
+
+    #[test]
+    fn correlation() {
+        let xs: Vec<f64> = (0..100).map(|i| i as f64 * 0.37).collect();
+        let r = lup!(Correlation: i by xs => {(xs[i], 3.0 * xs[i] + 1.0)});
+        assert!((r - 1.0).abs() < 1e-12);
+        let r = lup!(Correlation: i by xs => {(xs[i], -0.5 * xs[i])});
+        assert!((r + 1.0).abs() < 1e-12);
+
+        let mut seed = 777u64;
+        let mut rand = || lcg(&mut seed);
+        let noise: Vec<(f64, f64)> = (0..10000).map(|_| (rand(), rand())).collect();
+        let r = lup!(Correlation: i by noise => {noise[i]});
+        assert!(r.abs() < 0.05);
+
+        assert!(lup!(Correlation: i by xs => {(xs[i], 2.0)}).is_nan());
+        assert!(lup!(Correlation: _i in 0..1 => {(1.0, 2.0)}).is_nan());
+    }
+
+    #[test]
+    fn rms() {
+        let xs = [3.0, -4.0, 0.0, 5.0];
+        let rms = lup!(Rms<f64>: i by xs => {xs[i]});
+        let manual = ((9.0 + 16.0 + 25.0) / 4.0f64).sqrt();
+        assert!((rms - manual).abs() < 1e-12);
+
+        let empty: [f32; 0] = [];
+        assert!(lup!(Rms<f32>: i by empty => {empty[i]}).is_nan());
+
+        let frames = [[1.0f32, -2.0], [1.0, 2.0], [-1.0, 0.0]];
+        let rms = lup!(Rms<[f32; 2]>: i by frames => {frames[i]});
+        assert_eq!(rms[0], 1.0);
+        assert!((rms[1] - (8.0f32 / 3.0).sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn entropy() {
+        let n = 8;
+        let h = lup!(Entropy: _i in 0..n => {1.0 / n as f64});
+        assert!((h - (n as f64).ln()).abs() < 1e-12);
+        let bits = lup!(EntropyBits: _i in 0..n => {1.0 / n as f64});
+        assert!((bits - 3.0).abs() < 1e-12);
+
+        let degenerate = [0.0, 1.0, 0.0];
+        assert_eq!(lup!(Entropy: i by degenerate => {degenerate[i]}), 0.0);
+    }
+
+    #[test]
+    fn softmax() {
+        let xs = [1000.0, 1001.5, 999.0, 1003.0];
+        let p = lup!(Softmax: i by xs => {xs[i]});
+        let sum: f64 = p.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-12);
+        let mut order: Vec<usize> = (0..4).collect();
+        order.sort_by(|&a, &b| xs[a].partial_cmp(&xs[b]).unwrap());
+        let mut order_p: Vec<usize> = (0..4).collect();
+        order_p.sort_by(|&a, &b| p[a].partial_cmp(&p[b]).unwrap());
+        assert_eq!(order, order_p);
+
+        assert_eq!(lup!(Softmax: _i in 0..2 => {-5.0}), vec![0.5, 0.5]);
+        assert_eq!(lup!(Softmax: _i in 0..0 => {0.0}), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn cum_prod() {
+        let rates = [1.05, 1.1, 0.9, 1.2];
+        let table = lup!(CumProd<f64>: i by rates => {rates[i]});
+        assert_eq!(table.len(), 4);
+        assert_eq!(table[0], 1.05);
+        assert_eq!(table[3], lup!(Prod<f64>: i by rates => {rates[i]}));
+
+        let empty: [f32; 0] = [];
+        assert_eq!(lup!(CumProd<f32>: i by empty => {empty[i]}), vec![]);
+
+        let xs = [2.0f32, 3.0, 0.0, 4.0];
+        assert_eq!(lup!(CumProd<f32>: i by xs => {xs[i]}), vec![2.0, 6.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn horner() {
+        let coeffs = [2.0, -3.0, 0.5, 4.0];
+        for &x in &[-2.0, 0.0, 1.5, 3.0] {
+            let direct = 2.0 * x * x * x - 3.0 * x * x + 0.5 * x + 4.0;
+            assert_eq!(lup!(Horner(x): i by coeffs => {coeffs[i]}), direct);
+        }
+        let empty: [f64; 0] = [];
+        assert_eq!(lup!(Horner(2.0): i by empty => {empty[i]}), 0.0);
+        assert_eq!(lup!(Horner(2.0): i in 0..3 => coeffs[i]), 2.0 * 4.0 - 3.0 * 2.0 + 0.5);
+        assert_eq!(lup!(Horner(1.0): _ in 0..3 => {1.0}), 3.0);
+    }
+
+    #[test]
+    fn bit_mask() {
+        let flags = [true, false, false, true, true, false, true];
+        let mask = lup!(BitMask<u64>: i by flags => {flags[i]});
+        assert_eq!(mask, 0b1011001);
+        let decoded: Vec<bool> = (0..flags.len()).map(|i| mask & (1 << i) != 0).collect();
+        assert_eq!(decoded, flags);
+
+        let mask = lup!(BitMask<u128>: i in 0..128 => {i % 100 == 0});
+        assert_eq!(mask, 1 | (1 << 100));
+
+        let mask = lup!(BitMask<Vec<u64>>: i in 0..200 => {i == 3 || i == 130});
+        assert_eq!(mask, vec![1 << 3, 0, 1 << 2]);
+        assert_eq!(lup!(BitMask<Vec<u64>>: i in 0..200 => {false}), vec![]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bit_mask_overflow() {
+        lup!(BitMask<u64>: i in 0..65 => {i == 64});
+    }
+
+    #[test]
+    fn summary() {
+        let mut seed = 4242u64;
+        let mut rand = || lcg(&mut seed);
+        let xs: Vec<f64> = (0..1000).map(|_| rand() * 20.0 - 10.0).collect();
+
+        let summary = lup!(Summary: i by xs => {xs[i]});
+        assert_eq!(summary.count, xs.len());
+        let n = xs.len() as f64;
+        let mean = lup!(Sum<f64>: i by xs => {xs[i]}) / n;
+        assert!((summary.mean - mean).abs() < 1e-12);
+        let variance = lup!(Sum<f64>: i by xs => {(xs[i] - mean) * (xs[i] - mean)}) / n;
+        assert!((summary.variance - variance).abs() < 1e-9);
+        let min = lup!(Min<_, f64>: i by xs => {xs[i]});
+        assert_eq!(summary.min.evidence, min.evidence);
+        assert_eq!(summary.min.value, min.value);
+        let max = lup!(Max<_, f64>: i by xs => {xs[i]});
+        assert_eq!(summary.max.evidence, max.evidence);
+        assert_eq!(summary.max.value, max.value);
+
+        let empty: [f64; 0] = [];
+        let summary = lup!(Summary: i by empty => {empty[i]});
+        assert_eq!(summary.count, 0);
+        assert!(summary.mean.is_nan());
+        assert!(summary.variance.is_nan());
+        assert!(summary.min.value.is_nan() && summary.min.evidence.is_none());
+        assert!(summary.max.value.is_nan() && summary.max.evidence.is_none());
+    }
+
+    #[test]
+    fn percentile() {
+        let xs = [7.0, 1.0, f64::NAN, 4.0, 10.0, 3.0];
+        let min = lup!(Min<_, f64>: i in 0..xs.len() => {xs[i]});
+        assert_eq!(lup!(Percentile(0.0): i by xs => {xs[i]}), min.value);
+        let max = lup!(Max<_, f64>: i in 0..xs.len() => {xs[i]});
+        assert_eq!(lup!(Percentile(1.0): i by xs => {xs[i]}), max.value);
+        // Sorted without NaN: 1, 3, 4, 7, 10.
+        assert_eq!(lup!(Percentile(0.5): i by xs => {xs[i]}), 4.0);
+        assert_eq!(lup!(Percentile(0.25): i by xs => {xs[i]}), 3.0);
+        assert_eq!(lup!(Percentile(0.875): i by xs => {xs[i]}), 8.5);
+        assert_eq!(lup!(Percentile: i by xs => {xs[i]}), 4.0);
+        let empty: [f64; 0] = [];
+        assert!(lup!(Percentile(0.5): i by empty => {empty[i]}).is_nan());
+    }
+
+    #[test]
+    #[should_panic]
+    fn percentile_out_of_range() {
+        Percentile::new(1.5);
+    }
+
+    #[test]
+    fn p2_quantile() {
+        let mut seed = 777u64;
+        let mut rand = || lcg(&mut seed);
+        let uniform: Vec<f64> = (0..20000).map(|_| rand()).collect();
+        let bell: Vec<f64> = (0..20000).map(|_| rand() + rand() + rand()).collect();
+        for q in [0.1, 0.25, 0.5, 0.75, 0.9] {
+            let exact = lup!(Percentile(q): i by uniform => {uniform[i]});
+            let approx = lup!(P2Quantile(q): i by uniform => {uniform[i]});
+            assert!((exact - approx).abs() < 0.01);
+            let exact = lup!(Percentile(q): i by bell => {bell[i]});
+            let approx = lup!(P2Quantile(q): i by bell => {bell[i]});
+            assert!((exact - approx).abs() < 0.03);
+        }
+
+        // Exact with fewer than five values.
+        let xs = [7.0, f64::NAN, 1.0, 4.0, 10.0];
+        for q in [0.0, 0.3, 0.5, 1.0] {
+            assert_eq!(lup!(P2Quantile(q): i by xs => {xs[i]}),
+                       lup!(Percentile(q): i by xs => {xs[i]}));
+        }
+        assert_eq!(lup!(P2Quantile: i by xs => {xs[i]}), 5.5);
+
+        // Exact with exactly five values, before the markers track the quantile.
+        let xs = [4.0, 1.0, 5.0, 3.0, 2.0];
+        for q in [0.0, 0.3, 0.5, 1.0] {
+            assert_eq!(lup!(P2Quantile(q): i by xs => {xs[i]}),
+                       lup!(Percentile(q): i by xs => {xs[i]}));
+        }
+        assert_eq!(lup!(P2Quantile(0.0): i by xs => {xs[i]}), 1.0);
+        assert_eq!(lup!(P2Quantile(1.0): i by xs => {xs[i]}), 5.0);
+        let empty: [f64; 0] = [];
+        assert!(lup!(P2Quantile(0.5): i by empty => {empty[i]}).is_nan());
+    }
+
+    #[test]
+    fn all_pairs() {
+        let intervals = [(0.0, 1.0), (2.0, 3.5), (5.0, 6.0), (3.0, 4.0), (7.0, 8.0)];
+        let disjoint = |(a0, a1): (f64, f64), (b0, b1): (f64, f64)| a1 <= b0 || b1 <= a0;
+        let res = lup!(AllPairs: (i, j) by intervals => {disjoint(intervals[i], intervals[j])});
+        assert!(!res.value);
+        assert_eq!(res.evidence, Some((1, 3)));
+
+        let res = lup!(AllPairs: (i, j) by intervals[..3] => {disjoint(intervals[i], intervals[j])});
+        assert!(res.value);
+        assert_eq!(res.evidence, None);
+
+        assert_eq!(lup!(Sum<f64>: (i, j) by intervals[..4] => {(i * 10 + j) as f64}), 54.0);
+    }
+
+    #[test]
+    fn min_pair() {
+        let points = [[0.0, 0.0], [5.0, 5.0], [1.0, 7.0], [5.5, 4.0], [9.0, 1.0]];
+        let dist = |a: [f64; 2], b: [f64; 2]| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+        let res = lup!(MinPair: (i, j) by points => {dist(points[i], points[j])});
+        assert_eq!(res.evidence, Some((1, 3)));
+        assert_eq!(res.value, dist(points[1], points[3]));
+
+        let res = lup!(MinPair: (i, j) by points[..1] => {dist(points[i], points[j])});
+        assert_eq!(res.evidence, None);
+        let empty: [[f64; 2]; 0] = [];
+        let res = lup!(MinPair: (i, j) by empty => {dist(empty[i], empty[j])});
+        assert_eq!(res.evidence, None);
+    }
+
+    #[test]
+    fn frequencies() {
+        let text = "hello, world";
+        let chars: Vec<char> = text.chars().collect();
+        let freq = lup!(Frequencies<char>: i by chars => {chars[i]});
+        assert_eq!(freq[&'l'], 3);
+        assert_eq!(freq[&'o'], 2);
+        assert_eq!(freq[&' '], 1);
+        assert_eq!(freq.get(&'z'), None);
+        assert_eq!(freq.values().sum::<usize>(), text.len());
+        let order: String = freq.keys().collect();
+        assert_eq!(order, "helo, wrd");
+
+        let freq = lup!(SortedFrequencies<char>: c in text.chars().filter(|c| c.is_alphabetic()) => {c});
+        let table: Vec<(char, usize)> = freq.into_iter().collect();
+        assert_eq!(table, vec![('d', 1), ('e', 1), ('h', 1), ('l', 3), ('o', 2), ('r', 1), ('w', 1)]);
+        let empty = lup!(Frequencies<char>: c in "".chars() => {c});
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn is_sorted() {
+        let xs = [1, 2, 2, 5, 9];
+        let res = lup!(IsSorted<_>: i by xs => {xs[i]});
+        assert!(res.value && res.evidence.is_none());
+        let res = lup!(IsStrictlySorted<_>: i by xs => {xs[i]});
+        assert_eq!((res.value, res.evidence), (false, Some(2)));
+
+        let xs = [1.0, 3.0, 2.0, 0.0];
+        let res = lup!(IsSorted<_>: i by xs => {xs[i]});
+        assert_eq!((res.value, res.evidence), (false, Some(2)));
+
+        assert!(lup!(IsSorted<_>: i by [4.0] => {4.0 + i as f64}).value);
+        let empty: [f64; 0] = [];
+        assert!(lup!(IsSorted<_>: i by empty => {empty[i]}).value);
+
+        let xs = [1.0, 2.0, f64::NAN, 3.0];
+        let res = lup!(IsSorted<_>: i by xs => {xs[i]});
+        assert_eq!((res.value, res.evidence), (false, Some(2)));
+        let res = lup!(IsSorted<_>: i by xs[2..] => {xs[i + 2]});
+        assert_eq!((res.value, res.evidence), (false, Some(0)));
+    }
+
+    #[test]
+    fn is_unique() {
+        let ids = [3, 8, 1, 4];
+        let res = lup!(IsUnique<_>: i by ids => {ids[i]});
+        assert!(res.value && res.evidence.is_none());
+
+        let ids = [3, 8, 1, 8, 5];
+        let res = lup!(IsUnique<_>: i by ids => {ids[i]});
+        assert_eq!((res.value, res.evidence), (false, Some((1, 3))));
+
+        // The pair `(0, 4)` starts earlier, but `(2, 3)` is completed first.
+        let names = ["a", "b", "c", "c", "a", "b"];
+        let res = lup!(IsUnique<_>: i by names => {names[i]});
+        assert_eq!((res.value, res.evidence), (false, Some((2, 3))));
+    }
+
+    #[test]
+    fn longest_run() {
+        let flags = [true, true, true, false, true, false, true, true];
+        let res = lup!(LongestRun: i by flags => {flags[i]});
+        assert_eq!((res.value, res.evidence), (3, Some((0, 3))));
+
+        let flags = [false, true, false, true, true, true, true, false, true];
+        let res = lup!(LongestRun: i by flags => {flags[i]});
+        assert_eq!((res.value, res.evidence), (4, Some((3, 7))));
+
+        let flags = [true, false, true, true, false, true, true, true];
+        let res = lup!(LongestRun: i by flags => {flags[i]});
+        assert_eq!((res.value, res.evidence), (3, Some((5, 8))));
+
+        // Ties keep the first run.
+        let flags = [false, true, true, false, true, true];
+        let res = lup!(LongestRun: i by flags => {flags[i]});
+        assert_eq!((res.value, res.evidence), (2, Some((1, 3))));
+
+        let res = lup!(LongestRun: _ in 0..5 => {false});
+        assert_eq!((res.value, res.evidence), (0, None));
+    }
+
+    #[test]
+    fn majority() {
+        let votes = [2, 7, 2, 2, 5, 2, 7];
+        assert_eq!(lup!(Majority<_>: i by votes => {votes[i]}), Some(2));
+
+        // 1 is the plurality, but not a majority.
+        let votes = [1, 1, 2, 3, 1, 4, 5];
+        let candidate = lup!(Majority<_>: i by votes => {votes[i]}).unwrap();
+        let count = votes.iter().filter(|&&v| v == candidate).count();
+        assert!(2 * count <= votes.len());
+
+        let empty: [u8; 0] = [];
+        assert_eq!(lup!(Majority<_>: i by empty => {empty[i]}), None);
+    }
+
+    #[test]
+    fn mat_mul() {
+        let translate = [
+            [1.0, 0.0, 0.0, 10.0],
+            [0.0, 1.0, 0.0, 20.0],
+            [0.0, 0.0, 1.0, 30.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let scale = [
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let transform = |m: [[f32; 4]; 4], p: [f32; 4]| {
+            let mut r = [0.0; 4];
+            for i in 0..4 {r[i] = (0..4).map(|k| m[i][k] * p[k]).sum()}
+            r
+        };
+        let node_path = [translate, scale];
+        let world = lup!(MatMul: i by node_path => {node_path[i]});
+        // The scale is applied first, then the translation.
+        assert_eq!(transform(world, [1.0, 2.0, 3.0, 1.0]), [12.0, 24.0, 36.0, 1.0]);
+        let world = lup!(MatMul: i in (0..2).rev() => {node_path[i]});
+        assert_eq!(transform(world, [1.0, 2.0, 3.0, 1.0]), [22.0, 44.0, 66.0, 1.0]);
+
+        let id = lup!(MatMul<f64>: _i in 0..0 => {[[0.0; 4]; 4]});
+        assert_eq!(id[2], [0.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn quat_prod() {
+        fn to_mat([x, y, z, w]: [f32; 4]) -> [[f32; 4]; 4] {
+            [
+                [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w), 0.0],
+                [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w), 0.0],
+                [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y), 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]
+        }
+        let h = core::f32::consts::FRAC_1_SQRT_2;
+        // 90 degrees around z, then 90 degrees around x.
+        let rotations = [[0.0, 0.0, h, h], [h, 0.0, 0.0, h]];
+        let q = lup!(QuatProd: i by rotations => {rotations[i]});
+        let m = to_mat(q);
+        let reference = lup!(MatMul: i by rotations => {to_mat(rotations[i])});
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((m[i][j] - reference[i][j]).abs() < 1e-5);
+            }
+        }
+
+        // Many small rotations drift without renormalization.
+        let (s, c) = (0.001f32.sin(), 0.001f32.cos());
+        let step = [s * 0.6, 0.0, s * 0.8, c];
+        let norm = |q: [f32; 4]| q.iter().map(|x| x * x).sum::<f32>();
+        let drifted = lup!(QuatProd: _i in 0..100_000 => {step});
+        let fixed = lup!(QuatProd(16): _i in 0..100_000 => {step});
+        assert!((norm(fixed) - 1.0).abs() < 1e-5);
+        assert!((norm(fixed) - 1.0).abs() < (norm(drifted) - 1.0).abs());
+        let fixed_with = lup!(QuatProd<f32> with 16: _i in 0..100_000 => {step});
+        assert_eq!(fixed, fixed_with);
+    }
+
+    #[test]
+    fn max_iter() {
+        // The first square above 1000 is found before the cap.
+        let res = lup!(MaxIter<Any<_>> with 100: i in 0u64.. => {i * i > 1000});
+        assert!(res.completed());
+        assert_eq!((res.inner.value, res.inner.evidence), (true, Some(32)));
+        assert_eq!(res.iterations, 33);
+
+        // No witness, so only the cap stops the search.
+        let res = lup!(MaxIter<Any<_>> with 100: i in 0u64.. => {i * i == 1000});
+        assert!(res.truncated);
+        assert!(!res.inner.value);
+        assert_eq!(res.iterations, 100);
+
+        let res = lup!(MaxIter<Sum<f64>> with 10: i in (1..).map(|i| i as f64) => {1.0 / i});
+        assert!(res.truncated);
+        assert_eq!(res.iterations, 10);
+        let res = lup!(MaxIter<Sum<f64>> with 10: i in 0..5 => {i as f64});
+        assert!(res.completed());
+        assert_eq!(res.inner, 10.0);
+        let res = lup!(MaxIter<Sum<f64>> with 0: i in 0..5 => {i as f64});
+        assert!(res.truncated);
+        assert_eq!((res.inner, res.iterations), (0.0, 0));
+
+        // Exactly as many values as the cap.
+        let res = lup!(MaxIter<Sum<f64>> with 5: i in 0..5 => {i as f64});
+        assert!(res.completed());
+        assert_eq!((res.inner, res.iterations), (10.0, 5));
+        let res = lup!(MaxIter<Sum<f64>> with 5: i in 0..6 => {i as f64});
+        assert!(res.truncated);
+        assert_eq!((res.inner, res.iterations), (10.0, 5));
+    }
+
+    #[test]
+    fn sorted_unique_sift() {
+        let tags = ["b", "a", "c", "a", "b", "d", "a"];
+        let keys = lup!(SortedUniqueSift<_>: i by tags => {tags[i]});
+        assert_eq!(keys, vec!["a", "b", "c", "d"]);
+        let again = lup!(SortedUniqueSift<_>: i by keys => {keys[i]});
+        assert_eq!(again, keys);
+        let ids = lup!(SortedUniqueSift<u32>: i in 0..10 => {(i as u32 * 7) % 4});
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        let empty = lup!(SortedUniqueSift<u32>: _i in 0..0 => {0});
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn max_ref() {
+        #[derive(Debug, PartialEq, PartialOrd)]
+        struct Big(String, [u8; 64]);
+        let items = vec![
+            Big("pear".into(), [0; 64]),
+            Big("apple".into(), [1; 64]),
+            Big("zucchini".into(), [2; 64]),
+            Big("fig".into(), [3; 64]),
+        ];
+        let max = lup!(MaxRef<_>: i by items => {&items[i]});
+        assert_eq!(max.evidence, Some(2));
+        assert!(core::ptr::eq(max.value.unwrap(), &items[2]));
+        let min = lup!(MinRef<_>: i by items => {&items[i]});
+        assert_eq!(min.evidence, Some(1));
+        assert_eq!(min.value.unwrap().0, "apple");
+
+        let empty: Vec<Big> = vec![];
+        let max = lup!(MaxRef<_>: i by empty => {&empty[i]});
+        assert!(max.evidence.is_none() && max.value.is_none());
+    }
+
+    #[test]
+    fn apply() {
+        struct Batch {items: Vec<u32>, capacity: usize}
+        let sprites = [3, 1, 4, 1, 5, 9, 2, 6];
+        let mut batch = Batch {items: vec![], capacity: 5};
+        lup!(Apply(&mut batch, |b: &mut Batch, s| {
+            b.items.push(s);
+            b.items.len() < b.capacity
+        }): i by sprites => {sprites[i]});
+        assert_eq!(batch.items, vec![3, 1, 4, 1, 5]);
+
+        let mut total = 0;
+        lup!(Apply(&mut total, |t: &mut u32, x: u32| {*t += x; true}): i by sprites => {sprites[i]});
+        assert_eq!(total, 31);
+    }
+
+    #[test]
+    fn sum_opt() {
+        let prices = [Some(2.5), None, Some(4.0), None, None, Some(1.5)];
+        let res = lup!(SumOpt<f64>: i by prices => {prices[i]});
+        assert_eq!(res, Tally {sum: 8.0, missing: 3, present: 3});
+
+        let res = lup!(SumOpt<f64>: _i in 0..4 => {None});
+        assert_eq!(res, Tally {sum: 0.0, missing: 4, present: 0});
+        let res = lup!(SumOpt<f32>: i in 0..5 => {if i % 2 == 0 {Some(i as f32)} else {None}});
+        assert_eq!(res, Tally {sum: 6.0, missing: 2, present: 3});
+    }
+
+    #[test]
+    fn max_min_option() {
+        let readings = [Some(3.0), None, Some(7.5), Some(-1.0), None];
+        let max = lup!(MaxOpt<_, f64>: i by readings => {readings[i]});
+        assert_eq!((max.value, max.evidence), (7.5, Some(2)));
+        let min = lup!(MinOpt<_, f64>: i by readings => {readings[i]});
+        assert_eq!((min.value, min.evidence), (-1.0, Some(3)));
+
+        let missing: [Option<f32>; 3] = [None; 3];
+        let max = lup!(MaxOpt<_, f32>: i by missing => {missing[i]});
+        assert!(max.value.is_nan() && max.evidence.is_none());
+        let min = lup!(MinOpt<_, f32>: i by missing => {missing[i]});
+        assert!(min.value.is_nan() && min.evidence.is_none());
+
+        // A missing value where the maximum would be.
+        let xs = [1.0, 9.0, 4.0];
+        let max = lup!(MaxOpt<_, f64>: i by xs => {if i == 1 {None} else {Some(xs[i])}});
+        assert_eq!((max.value, max.evidence), (4.0, Some(2)));
+
+        // Driving the loops by hand does not need the body type.
+        let mut m = Max::<usize, f64>::start();
+        m.it(0, 1.0);
+        m.it(1, 3.0);
+        assert_eq!(m.unwrap().evidence, Some(1));
+        let mut m = MinOpt::<usize, f64>::start();
+        m.it(0, Some(1.0));
+        m.it(1, None);
+        assert_eq!(m.unwrap().evidence, Some(0));
+
+        // Nested, where an inner secret without a value is skipped.
+        let res = lup!(MaxOpt<(usize, usize), f64>: i in 0..3 => {
+            let row = lup!(Max<usize, f64>: j in 0..3 => {(i * 3 + j) as f64});
+            Secret {evidence: row.evidence, value: if i == 2 {None} else {Some(row.value)}}
+        });
+        assert_eq!((res.value, res.evidence), (5.0, Some((1, 2))));
+        let res = lup!(MinOpt<(usize, usize), f64>: i in 0..3 => {
+            let row = lup!(Min<usize, f64>: j in 0..3 => {(i * 3 + j) as f64});
+            Secret {evidence: row.evidence, value: if i == 0 {None} else {Some(row.value)}}
+        });
+        assert_eq!((res.value, res.evidence), (3.0, Some((1, 0))));
+    }
+
+    #[test]
+    fn secret_merge() {
+        let xs = [3.0, 9.0, 1.0, 9.0, 4.0, 0.5, 7.0, 9.0, 2.0, 0.5];
+        let chunks = [0..3, 3..7, 7..10];
+        let parts: Vec<Secret<usize, f64>> = chunks.iter().map(|r| {
+            let chunk = &xs[r.clone()];
+            lup!(Max<_, f64>: i by chunk => {chunk[i]})
+        }).collect();
+        let single = lup!(Max<_, f64>: i by xs => {xs[i]});
+        let merged = parts.iter().cloned().zip(chunks.iter())
+            .fold(Secret {evidence: None, value: f64::NAN}, |acc, (part, r)| {
+                Secret::merge_max(acc, part, r.start)
+            });
+        assert_eq!((merged.value, merged.evidence), (single.value, single.evidence));
+        assert_eq!(merged.evidence, Some(1));
+        // Merging in reverse order gives the same result.
+        let empty = Secret {evidence: None, value: f64::NAN};
+        let merged = Secret::merge_max(empty, parts[2].clone(), 7);
+        let merged = Secret::merge_max(merged, parts[1].clone(), 3);
+        let merged = Secret::merge_max(merged, parts[0].clone(), 0);
+        assert_eq!(merged.evidence, Some(1));
+
+        let min = Secret::merge_min(lup!(Min<_, f64>: i by xs[..5] => {xs[i]}),
+                                    lup!(Min<_, f64>: i by xs[5..] => {xs[5 + i]}), 5);
+        assert_eq!((min.value, min.evidence), (0.5, Some(5)));
+
+        let any = Secret::merge_any(lup!(Any<_>: i by xs[..5] => {xs[i] > 8.0}),
+                                    lup!(Any<_>: i by xs[5..] => {xs[5 + i] > 8.0}), 5);
+        assert_eq!((any.value, any.evidence), (true, Some(1)));
+        let any = Secret::merge_any(lup!(Any<_>: i by xs[..5] => {xs[i] < 1.0}),
+                                    lup!(Any<_>: i by xs[5..] => {xs[5 + i] < 1.0}), 5);
+        assert_eq!((any.value, any.evidence), (true, Some(5)));
+        let all = Secret::merge_all(lup!(All<_>: i by xs[..5] => {xs[i] > 0.0}),
+                                    lup!(All<_>: i by xs[5..] => {xs[5 + i] > 0.0}), 5);
+        assert_eq!((all.value, all.evidence), (true, None));
+        let all = Secret::merge_all(lup!(All<_>: i by xs[..5] => {xs[i] > 1.0}),
+                                    lup!(All<_>: i by xs[5..] => {xs[5 + i] > 1.0}), 5);
+        assert_eq!((all.value, all.evidence), (false, Some(2)));
+    }
+
+    #[test]
+    fn spread() {
+        let frame_ms = [16.6, 16.9, 15.8, 33.4, 16.7, f64::NAN, 17.0];
+        let res = lup!(Spread<_, f64>: i by frame_ms => {frame_ms[i]});
+        let min = lup!(Min<_, f64>: i by frame_ms => {frame_ms[i]});
+        let max = lup!(Max<_, f64>: i by frame_ms => {frame_ms[i]});
+        assert_eq!(res.value, max.value - min.value);
+        assert_eq!(res.evidence, Some((min.evidence.unwrap(), max.evidence.unwrap())));
+        assert_eq!(res.evidence, Some((2, 3)));
+
+        let res = lup!(Spread<_, f32>: _i in 0..1 => {2.5});
+        assert_eq!((res.value, res.evidence), (0.0, Some((0, 0))));
+        let res = lup!(Spread<_, f32>: _i in 0..0 => {2.5});
+        assert!(res.value.is_nan() && res.evidence.is_none());
+    }
+
+    #[test]
+    fn min_gap() {
+        let times = [0.0, 2.5, 3.0, 7.0, 7.75, 10.0];
+        let gap = lup!(MinGap: i by times => {times[i]});
+        assert_eq!((gap.value, gap.evidence), (0.5, Some(2)));
+
+        let times = [1.0, 4.0, 4.0, 6.0];
+        let gap = lup!(MinGap: i by times => {times[i]});
+        assert_eq!((gap.value, gap.evidence), (0.0, Some(2)));
+
+        let gap = lup!(MinGap: i by [1.0] => {1.0 + i as f64});
+        assert!(gap.value.is_nan() && gap.evidence.is_none());
+
+        // NaN values are skipped, also at the start.
+        let times = [f64::NAN, 1.0, 4.0, f64::NAN, 4.5, 9.0];
+        let gap = lup!(MinGap: i by times => {times[i]});
+        assert_eq!((gap.value, gap.evidence), (0.5, Some(4)));
+        let gap = lup!(MinGap: i by [f64::NAN, 2.0] => {[f64::NAN, 2.0][i]});
+        assert!(gap.value.is_nan() && gap.evidence.is_none());
+    }
+
+    #[test]
+    fn l1_linf() {
+        let a = [1.0, -2.0, 3.5, 0.0];
+        let b = [1.5, 2.0, 3.0, -1.0];
+        let l1 = lup!(L1: i by a => {(a[i], b[i])});
+        assert_eq!(l1, 0.5 + 4.0 + 0.5 + 1.0);
+        let linf = lup!(Linf<_>: i by a => {(a[i], b[i])});
+        assert_eq!((linf.value, linf.evidence), (4.0, Some(1)));
+
+        let empty: [f64; 0] = [];
+        assert_eq!(lup!(L1: i by empty => {(empty[i], empty[i])}), 0.0);
+        let linf = lup!(Linf<_>: i by empty => {(empty[i], empty[i])});
+        assert_eq!((linf.value, linf.evidence), (0.0, None));
+
+        // A NaN difference propagates in `L1` and is skipped by `Linf`, also at the start.
+        let a = [f64::NAN, 1.0, 5.0];
+        let b = [0.0, 3.0, 4.0];
+        assert!(lup!(L1: i by a => {(a[i], b[i])}).is_nan());
+        let linf = lup!(Linf<_>: i by a => {(a[i], b[i])});
+        assert_eq!((linf.value, linf.evidence), (2.0, Some(1)));
+        let linf = lup!(Linf<_>: i by a[..1] => {(a[i], b[i])});
+        assert_eq!((linf.value, linf.evidence), (0.0, None));
+    }
+
+    #[test]
+    fn hamming() {
+        let a = "GATTACA".as_bytes();
+        assert_eq!(lup!(Hamming: i by a => {(a[i], a[i])}), 0);
+        let b = "CTAATGT".as_bytes();
+        assert_eq!(lup!(Hamming: i by a => {(a[i], b[i])}), a.len());
+        let c = "GACTATA".as_bytes();
+        assert_eq!(lup!(Hamming: i by a => {(a[i], c[i])}), 2);
+
+        let x: [u64; 3] = [0xff00_ff00_1234_5678, 0, u64::MAX];
+        let y: [u64; 3] = [0x0f00_ff0f_1234_5679, 1 << 63, 0xffff];
+        let reference = lup!(Hamming: i in 0..3 * 64 => {
+            ((x[i / 64] >> (i % 64)) & 1, (y[i / 64] >> (i % 64)) & 1)
+        });
+        assert_eq!(lup!(HammingBits: i by x => {(x[i], y[i])}), reference);
+        assert_eq!(reference, 4 + 4 + 1 + 1 + 48);
+    }
+
+    #[test]
+    fn for_each() {
+        let mut queue = [3, 5, 8, 0, 2, 7];
+        let handled = lup!(ForEach: i by queue => {
+            queue[i] *= 10;
+            queue[i] != 0
+        });
+        assert_eq!(handled, 4);
+        assert_eq!(queue, [30, 50, 80, 0, 2, 7]);
+
+        let empty: [u8; 0] = [];
+        assert_eq!(lup!(ForEach: _i by empty => {true}), 0);
+
+        // Driven by hand, `unwrap` and `finish` agree on the count.
+        let mut each = ForEach::start();
+        for (i, &x) in queue.iter().enumerate() {
+            if !each.it(i, x != 0) {break}
+        }
+        assert_eq!(each.clone().unwrap(), 4);
+        assert_eq!(each.finish(4), 4);
+    }
+
+    #[test]
+    fn flat_vector() {
+        let (h, w) = (3, 4);
+        let img: Vec<f32> = lup!(FlatVector(h, w): y in 0..h, x in 0..w => {
+            (x + y) as f32 / (h + w - 2) as f32
+        });
+        assert_eq!(img.len(), 12);
+        assert_eq!(img[0], 0.0);
+        assert_eq!(img[w + 2], 0.6);
+        assert_eq!(img[2 * w + 3], 1.0);
+
+        let rows = [[1u8, 2], [3, 4]];
+        let flat: Vec<u8> = lup!(FlatVector(2, 2): i, j by rows => {rows[i][j]});
+        assert_eq!(flat, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "FlatVector: index (0, 3) out of range for (2, 3)")]
+    fn flat_vector_out_of_range() {
+        let _: Vec<f32> = lup!(FlatVector(2, 3): i in 0..2, j in 0..4 => {(i * j) as f32});
+    }
+
+    #[test]
+    fn sparse_sift() {
+        let xs = [0.0, 0.0, 1.5, 0.0, -2.0];
+        let changed = lup!(SparseSift<Vec<_>>: i by xs => {
+            if xs[i] != 0.0 {Some(xs[i])} else {None}
+        });
+        assert_eq!(changed, vec![(2, 1.5), (4, -2.0)]);
+
+        let mut grid = [[0u8; 8]; 8];
+        grid[1][6] = 3;
+        grid[5][2] = 7;
+        grid[5][7] = 1;
+        let cells = lup!(SparseSift<Vec<_>>: i, j by grid => {
+            if grid[i][j] != 0 {Some(grid[i][j])} else {None}
+        });
+        assert_eq!(cells, vec![((1, 6), 3), ((5, 2), 7), ((5, 7), 1)]);
+    }
+
+    #[test]
+    fn value_any() {
+        let words: Vec<String> = "the quick brown jumping fox leaps".split(' ').map(String::from).collect();
+        let found = lup!(ValueAny<_>: (i, w) in words.into_iter().enumerate() => {(w.len() > 5, w)});
+        assert!(found.value);
+        assert_eq!(found.evidence, Some(String::from("jumping")));
+
+        let found = lup!(ValueAny<_>: i in 0..10 => {(i > 20, i)});
+        assert!(!found.value && found.evidence.is_none());
+    }
+
+    #[test]
+    fn timed() {
+        let xs: Vec<f64> = (0..10000).map(|i| (i as f64).sin()).collect();
+        let res = lup!(Timed<Sum<f64>>: i by xs => {xs[i].abs().sqrt()});
+        assert_eq!(res.inner, lup!(Sum<f64>: i by xs => {xs[i].abs().sqrt()}));
+        assert_eq!(res.iterations, xs.len());
+        assert!(res.elapsed > std::time::Duration::new(0, 0));
+
+        let res = lup!(Timed<Any<_>>: i by xs => {xs[i] > 0.5});
+        assert_eq!(res.inner.evidence, Some(1));
+        assert_eq!(res.iterations, 2);
+    }
+
+    #[test]
+    fn map_lup() {
+        let xs: [f64; 5] = [3.0, -7.5, 2.0, 7.0, -1.0];
+        let res = lup!(MapLup<Max<_, f64>, Abs>: i by xs => {xs[i]});
+        let manual = lup!(Max<_, f64>: i by xs => {xs[i].abs()});
+        assert_eq!((res.value, res.evidence), (manual.value, manual.evidence));
+        assert_eq!((res.value, res.evidence), (7.5, Some(1)));
+
+        assert_eq!(lup!(MapLup<Sum<f64>, Sq>: i by xs => {xs[i]}), 9.0 + 56.25 + 4.0 + 49.0 + 1.0);
+        assert_eq!(lup!(MapLup<Sum<f32>, Recip>: i in 1..3 => {i as f32 * 2.0}), 0.75);
+    }
+
+    #[test]
+    fn filter_lup() {
+        let xs = [1.0, f64::NAN, -2.0, f64::INFINITY, 4.0];
+        assert_eq!(lup!(FilterLup<Sum<f64>, Finite>: i by xs => {xs[i]}), 3.0);
+        assert_eq!(lup!(FilterLup<Sum<f64>, Positive>: i by xs[..2] => {xs[i]}), 1.0);
+        let max = lup!(FilterLup<Max<_, f64>, Finite>: i by xs => {xs[i]});
+        assert_eq!((max.value, max.evidence), (4.0, Some(4)));
+
+        // Counts the iterations with the finish hook.
+        struct Count;
+        impl Lup<usize, f64> for Count {
+            type Inner = usize;
+            fn start() -> Count {Count}
+            fn it(&mut self, _: usize, _: f64) -> bool {true}
+            fn unwrap(self) -> usize {0}
+            fn finish(self, iterations: usize) -> usize {iterations}
+        }
+        assert_eq!(lup!(Count: i by xs => {xs[i]}), 5);
+        assert_eq!(lup!(FilterLup<Count, Finite>: i by xs => {xs[i]}), 3);
+
+        // A selective filter does not reserve space for all the values.
+        let list = lup!(FilterLup<Sift<Vec<f64>>, Positive>: i in 0..1000 => {
+            if i % 100 == 0 {1.0} else {-1.0}
+        });
+        assert_eq!(list.len(), 10);
+        assert!(list.capacity() < 1000);
+    }
+
+    #[test]
+    fn skip_take_lup() {
+        let mut evaluated = 0;
+        let res = lup!(TakeLup<Any<usize>, 1000>: i in 0..1_000_000 => {
+            evaluated += 1;
+            i == 5000
+        });
+        assert!(!res.value);
+        assert_eq!(evaluated, 1000);
+
+        let xs = [9.0, 1.0, 4.0, 7.0, 2.0];
+        let max = lup!(SkipLup<Max<_, f64>, 2>: i by xs => {xs[i]});
+        assert_eq!((max.value, max.evidence), (7.0, Some(3)));
+        assert_eq!(lup!(TakeLup<Sum<f64>, 2>: i by xs => {xs[i]}), 10.0);
+        assert_eq!(lup!(TakeLup<SkipLup<Sum<f64>, 1>, 3>: i by xs => {xs[i]}), 5.0);
+        assert_eq!(lup!(TakeLup<Sum<f64>, 0>: i by xs => {xs[i]}), 0.0);
+
+        // The size hint is forwarded to the inner loop.
+        let list = lup!(SkipLup<Sift<Vec<usize>>, 30>: i in 0..100 => {i});
+        assert_eq!((list.len(), list.capacity()), (70, 70));
+        let list = lup!(SkipLup<Sift<Vec<usize>>, 300>: i in 0..100 => {i});
+        assert_eq!(list.capacity(), 0);
+        let list = lup!(TakeLup<Sift<Vec<usize>>, 30>: i in 0..100 => {i});
+        assert_eq!((list.len(), list.capacity()), (30, 30));
+        let list = lup!(TakeLup<Sift<Vec<usize>>, 300>: i in 0..100 => {i});
+        assert_eq!((list.len(), list.capacity()), (100, 100));
+    }
+
+    #[test]
+    fn lup_with() {
+        let xs = [1.0, 2.5, 4.0];
+        assert_eq!(lup!(Sum<f64> with 10.0: i by xs => {xs[i]}), 17.5);
+        assert_eq!(lup!(Sum<f64> with 10.0: _ in 0..3 => {1.0}), 13.0);
+        assert_eq!(lup!(Sum<[f32; 2]> with [1.0, 1.0]: i by xs => [xs[i] as f32, 0.0]), [8.5, 1.0]);
+        assert_eq!(lup!(Prod<f64> with f64::from(2u8): i by xs with x = xs[i] => {x}), 20.0);
+        let empty: [f64; 0] = [];
+        assert_eq!(lup!(Sum<f64> with 3.0: i by empty => {empty[i]}), 3.0);
+
+        let coeffs = [1.0, 0.0, -1.0];
+        assert_eq!(lup!(Horner with 3.0: i by coeffs => {coeffs[i]}), 8.0);
+        let grid: Vec<u8> = lup!(FlatVector<_> with (2, 2): i in 0..2, j in 0..2 => {(i * 2 + j) as u8});
+        assert_eq!(grid, vec![0, 1, 2, 3]);
+        assert_eq!(lup!(Percentile with 1.0: i by coeffs => {coeffs[i]}), 1.0);
+    }
+
+    #[test]
+    fn len() {
+        struct Arena(Vec<f64>);
+        impl core::ops::Index<usize> for Arena {
+            type Output = f64;
+            fn index(&self, i: usize) -> &f64 {&self.0[i]}
+        }
+        impl Len for Arena {
+            fn lup_len(&self) -> usize {self.0.len()}
+        }
+
+        let arena = Arena(vec![1.0, 5.0, 2.0]);
+        assert_eq!(lup!(Sum<f64>: i by arena => {arena[i]}), 8.0);
+        let max = lup!(Max<_, f64>: i by arena => {arena[i]});
+        assert_eq!(max.evidence, Some(1));
+        assert_eq!(lup!(Sum<f64>: i by arena skip 1 => {arena[i]}), 7.0);
+        let all = lup!(AllPairs: (i, j) by arena => {arena[i] != arena[j]});
+        assert!(all.value);
+
+        let ring: std::collections::VecDeque<f64> = (0..4).map(|i| i as f64).collect();
+        assert_eq!(lup!(Sum<f64>: i by ring => {ring[i]}), 6.0);
+    }
+
+    #[test]
+    fn body_ascription() {
+        // `u8` converts into several types, so the body type is ambiguous without `-> f32`.
+        let xs = [3u8, 9, 2];
+        let max = lup!(Max<_, _> -> f32: i by xs => {xs[i].into()});
+        assert_eq!((max.value, max.evidence), (9.0, Some(1)));
+        let min = lup!(Min<_, _> -> f64: i by xs => xs[i].into());
+        assert_eq!((min.value, min.evidence), (2.0, Some(2)));
+        assert_eq!(lup!(Sum<_> -> f64: i by xs with x = xs[i] => {x.into()}), 14.0);
+    }
+
+    #[test]
+    fn secret_evidence_accessors() {
+        let grid = [[1, 2], [3, 9], [4, 5]];
+        let offset = 10;
+        let mut max = lup!(Max<_, f64>: i, j by grid[1..] => {grid[i + 1][j] as f64});
+        if let Some(e) = max.evidence_mut() {e.0 += offset}
+        assert_eq!(max.evidence, Some((10, 1)));
+
+        assert_eq!(max.take_evidence(), Some((10, 1)));
+        assert_eq!(max.evidence, None);
+        assert_eq!(max.value, 9.0);
+        assert_eq!(max.take_evidence(), None);
+        assert_eq!(max.evidence_mut(), None);
+    }
+
+    #[test]
+    fn unordered_accum() {
+        let xs: Vec<f64> = (0..1003).map(|i| (i as f64 * 0.37).sin()).collect();
+        let naive = xs.iter().fold(0.0, |a, x| a + x);
+        // Float sums keep the sequential order unless they opt in.
+        assert_eq!(lup!(Sum<f64>: i by xs => {xs[i]}), naive);
+        let sum = lup!(SumUnordered<f64>: i by xs => {xs[i]});
+        assert!((sum - naive).abs() < 1e-9);
+
+        // Integer sums and products are exact.
+        for n in [0u64, 1, 2, 3, 4, 5, 7, 8, 1003] {
+            assert_eq!(lup!(Sum<u64>: i in 0..n => {i * i}), (0..n).map(|i| i * i).sum::<u64>());
+            assert_eq!(lup!(Sum<i32>: i in 0..n => {i as i32 - 500}),
+                       (0..n).map(|i| i as i32 - 500).sum::<i32>());
+        }
+        assert_eq!(lup!(Prod<u64>: i in 1..21 => {i}), (1..21).product::<u64>());
+
+        // `break` and `continue` in the body behave like in the sequential loop.
+        let mut seen = vec![];
+        lup!(For: i in 0..10 => {
+            if i == 6 {break}
+            if i % 2 == 1 {continue}
+            seen.push(i);
+        });
+        assert_eq!(seen, vec![0, 2, 4]);
+        assert_eq!(lup!(Sum<u64>: i in 0..12 => {if i % 4 == 3 {continue} i}), 45);
+        let sum = lup!(Sum<u64>: i in 0..12 => {
+            if i == 9 {break}
+            if i % 4 == 3 {continue}
+            i
+        });
+        assert_eq!(sum, 26);
+
+        let mask = lup!(BitMask<u64>: i in 0..64 => {i % 3 == 0});
+        let naive = (0..64).filter(|i| i % 3 == 0).fold(0u64, |m, i| m | 1 << i);
+        assert_eq!(mask, naive);
+        let mask = lup!(BitMask<Vec<u64>>: i in 0..130 => {i == 1 || i == 129});
+        assert_eq!(mask, vec![2, 0, 2]);
+
+        // Counts the iterations with the finish hook.
+        struct Count;
+        impl Lup<usize, ()> for Count {
+            type Inner = usize;
+            fn start() -> Count {Count}
+            fn it(&mut self, _: usize, _: ()) -> bool {true}
+            fn unwrap(self) -> usize {0}
+            fn finish(self, iterations: usize) -> usize {iterations}
+        }
+        impl UnorderedAccum<usize, ()> for Count {
+            fn merge(&mut self, _: Count) {}
+        }
+        for n in 0..9 {
+            assert_eq!(lup!(Count: _i in 0..n => {}), n);
+        }
+
+        // Loops with evidence keep the order.
+        let max = lup!(Max<_, f64>: i in 0..8 => {(i % 3) as f64});
+        assert_eq!(max.evidence, Some(2));
+    }
+
+    #[test]
+    fn any_scan() {
+        let xs = [4, 8, 15, 16, 23, 42];
+        let res = lup!(AnyScan<_>: i by xs => {xs[i] == 4});
+        assert_eq!((res.secret.value, res.secret.evidence, res.scanned), (true, Some(0), 1));
+        let res = lup!(AnyScan<_>: i by xs => {xs[i] == 16});
+        assert_eq!((res.secret.value, res.secret.evidence, res.scanned), (true, Some(3), 4));
+        let res = lup!(AnyScan<_>: i by xs => {xs[i] == 7});
+        assert_eq!((res.secret.value, res.secret.evidence, res.scanned), (false, None, 6));
+
+        let grid = [[1, 2, 3], [4, 5, 6], [7, 8, 9]];
+        let res = lup!(AnyScan<_>: i, j by grid => {grid[i][j] == 5});
+        assert_eq!((res.secret.evidence, res.scanned), (Some((1, 1)), 5));
+        let res = lup!(AnyScan<_>: i, j by grid => {grid[i][j] == 0});
+        assert_eq!((res.secret.evidence, res.scanned), (None, 9));
+        let cube = [grid, grid];
+        let res = lup!(AnyScan<_>: i, j, k by cube => {cube[i][j][k] == 2 && i == 1});
+        assert_eq!((res.secret.evidence, res.scanned), (Some((1, 0, 1)), 11));
+    }
+
+    #[test]
+    fn per_axis() {
+        let errors = [[0.5, 2.0, 0.1], [3.0, 1.0, 0.2], [1.0, 0.5, 0.9], [0.2, 2.5, 0.3]];
+        let max = lup!(MaxPerAxis<_, f32, 3>: i by errors => {errors[i]});
+        let evidence: Vec<_> = max.iter().map(|s| (s.value, s.evidence)).collect();
+        assert_eq!(evidence, vec![(3.0, Some(1)), (2.5, Some(3)), (0.9, Some(2))]);
+        let min = lup!(MinPerAxis<_, f32, 3>: i by errors => {errors[i]});
+        let evidence: Vec<_> = min.iter().map(|s| (s.value, s.evidence)).collect();
+        assert_eq!(evidence, vec![(0.2, Some(3)), (0.5, Some(2)), (0.1, Some(0))]);
+
+        let max = lup!(MaxPerAxis<_, f64, 2>: i in 0..0 => {[i as f64; 2]});
+        assert!(max[0].value.is_nan() && max[1].evidence.is_none());
+    }
+
+    #[test]
+    fn normalize() {
+        let xs = [2.0, 6.0, 4.0, 10.0];
+        assert_eq!(lup!(Normalize: i by xs => {xs[i]}), vec![0.0, 0.5, 0.25, 1.0]);
+        assert_eq!(lup!(Normalize: _i in 0..3 => {7.5}), vec![0.0; 3]);
+        let empty: [f64; 0] = [];
+        assert_eq!(lup!(Normalize: i by empty => {empty[i]}), vec![]);
+
+        let xs = [f64::NAN, 1.0, 3.0, f64::NAN, 2.0];
+        let norm = lup!(Normalize: i by xs => {xs[i]});
+        assert!(norm[0].is_nan() && norm[3].is_nan());
+        assert_eq!((norm[1], norm[2], norm[4]), (0.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn z_score() {
+        let mut seed = 4242u64;
+        let mut rand = || lcg(&mut seed);
+        let xs: Vec<f64> = (0..1000).map(|_| rand() * 50.0 + 20.0).collect();
+        let z = lup!(ZScore: i by xs => {xs[i]});
+        assert_eq!(z.len(), xs.len());
+        let mean = lup!(Sum<f64>: i by z => {z[i]}) / z.len() as f64;
+        let var = lup!(Sum<f64>: i by z => {(z[i] - mean) * (z[i] - mean)}) / z.len() as f64;
+        assert!(mean.abs() < 1e-12);
+        assert!((var.sqrt() - 1.0).abs() < 1e-12);
+
+        assert_eq!(lup!(ZScore: i by [1.0, 3.0] => {[1.0, 3.0][i]}), vec![-1.0, 1.0]);
+        assert_eq!(lup!(ZScore: _i in 0..3 => {7.5}), vec![0.0; 3]);
+        assert_eq!(lup!(ZScore: _i in 0..0 => {0.0}), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn clamped_sum() {
+        let damage = [30u32, 25, 40, 10];
+        let res = lup!(ClampedSum(200): i by damage => {damage[i]});
+        assert_eq!((res.value, res.evidence), (105, None));
+        let res = lup!(ClampedSum(95): i by damage => {damage[i]});
+        assert_eq!((res.value, res.evidence), (95, Some(2)));
+        let res = lup!(ClampedSum(60): i by damage => {damage[i]});
+        assert_eq!((res.value, res.evidence), (60, Some(2)));
+        let res = lup!(ClampedSum<u8> with 250: _i in 0..3 => {200});
+        assert_eq!((res.value, res.evidence), (250, Some(1)));
+
+        let pool: [f64; 3] = [0.5, 1.25, 2.0];
+        let res = lup!(ClampedSum(1.75): i by pool => {pool[i]});
+        assert_eq!((res.value, res.evidence), (1.75, Some(1)));
+        let res = lup!(ClampedSum(10.0): i by pool => {pool[i]});
+        assert_eq!((res.value, res.evidence), (3.75, None));
+    }
+
+    #[test]
+    fn any_budget() {
+        let res = lup!(AnyBudget(1000): i in 0..1_000_000 => {i * i == 144});
+        assert_eq!(res, SearchResult::Found(12));
+        let res = lup!(AnyBudget(1000): i in 0..1_000_000 => {i == 5000});
+        assert_eq!(res, SearchResult::Exhausted);
+        let res = lup!(AnyBudget(1000): i in 0..100 => {i == 5000});
+        assert_eq!(res, SearchResult::NotFound);
+        let res = lup!(AnyBudget<usize> with 10: i in 0..100 => {i == 9});
+        assert_eq!(res, SearchResult::Found(9));
+        let res = lup!(AnyBudget<usize> with 10: i in 0..100 => {i == 10});
+        assert_eq!(res, SearchResult::Exhausted);
+        let res = lup!(AnyBudget(5): i in 0..4 => {i == 5});
+        assert_eq!(res, SearchResult::NotFound);
+        let res = lup!(AnyBudget(5): i in 0..5 => {i == 5});
+        assert_eq!(res, SearchResult::Exhausted);
+        let res = lup!(AnyBudget(5): i in 0..5 => {i == 4});
+        assert_eq!(res, SearchResult::Found(4));
+
+        // The body is never evaluated after the budget is spent.
+        let mut evaluated = 0;
+        let res = lup!(AnyBudget(3): i in 0..10 => {evaluated += 1; i == 3});
+        assert_eq!(res, SearchResult::Exhausted);
+        assert_eq!(evaluated, 3);
+        let res = lup!(AnyBudget(0): i in 0..5 => {i == 0});
+        assert_eq!(res, SearchResult::Exhausted);
+        let res = lup!(AnyBudget(0): i in 0..0 => {i == 0});
+        assert_eq!(res, SearchResult::Exhausted);
+        let res = lup!(AnyBudget<_>: i in 0..100 => {i == 10});
+        assert_eq!(res, SearchResult::Found(10));
+    }
+
+    #[test]
+    fn flow() {
+        struct Count(usize);
+        impl<I, T> Lup<I, T> for Count {
+            type Inner = usize;
+            fn start() -> Count {Count(0)}
+            fn it(&mut self, _: I, _: T) -> bool {self.0 += 1; true}
+            fn unwrap(self) -> usize {self.0}
+        }
+
+        let grid = [[1.0, 2.0, -1.0], [3.0, 0.0, 4.0], [5.0, 6.0, 7.0]];
+        let body = |x: f64| if x == 0.0 {Flow::Break(x)} else if x < 0.0 {Flow::Skip}
+                            else {Flow::Continue(x)};
+
+        // Skips -1, breaks at 0 after adding it, never reaches the last row.
+        assert_eq!(lup!(Sum<f64>: i, j by grid => flow {body(grid[i][j])}), 6.0);
+        assert_eq!(lup!(Sum<f64>: i in 0..3, j in 0..3 => flow {body(grid[i][j])}), 6.0);
+        assert_eq!(lup!(Count: i, j by grid => flow {body(grid[i][j])}), 2);
+        let rows: Vec<Vec<f64>> = lup!(Sift<_>: i, j by grid => flow {body(grid[i][j])});
+        assert_eq!(rows, vec![vec![1.0, 2.0], vec![3.0, 0.0]]);
+
+        // Continue on every item is the same as a plain loop.
+        assert_eq!(lup!(Sum<f64>: i, j by grid => flow {Flow::Continue(grid[i][j])}),
+                   lup!(Sum<f64>: i, j by grid => {grid[i][j]}));
+        // A row where everything is skipped still counts as a row.
+        assert_eq!(lup!(Count: _i in 0..3, _j in 0..3 => flow {Flow::<()>::Skip}), 3);
+    }
+
+    #[test]
+    fn short() {
+        let list = lup!(Sift<_>: i in 0..3, j in 0..3, k in 0..3 => {
+            i as f32 + j as f32 + k as f32
+        });
+        let a = lup!(Sum<_>: i, j, k by list => {list[i][j][k]});
+        assert_eq!(a, 81.0);
+
+        let tensor = lup!(Sift<_>: i in 0..2, j in 0..2, k in 0..2, l in 0..3 => {
+            i as f32 + j as f32 + k as f32 + l as f32
+        });
+        let b = lup!(Sum<_>: i, j, k, l by tensor => {tensor[i][j][k][l]});
+        assert_eq!(b, 60.0);
+
+        let c = lup!(Any<_>: i, j, k, l by tensor => {tensor[i][j][k][l] == 4.0});
+        assert_eq!(c.evidence, Some((0, 1, 1, 2)));
+
+        let d = lup!(All<_>: i, j, k, l by tensor => {tensor[i][j][k][l] < 3.0});
+        assert_eq!(d.evidence, Some((0, 0, 1, 2)));
+
+        let e = lup!(Max<_, f32>: i, j, k, l by tensor => {tensor[i][j][k][l]});
+        assert_eq!(e.value, 5.0);
+        assert_eq!(e.evidence, Some((1, 1, 1, 2)));
+
+        let f = lup!(Min<_, f32>: i, j, k, l by tensor => {tensor[i][j][k][l]});
+        assert_eq!(f.evidence, Some((0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn map_keys() {
+        use std::collections::BTreeMap;
+
+        let mut scores = BTreeMap::new();
+        scores.insert("alice".to_string(), 3.0);
+        scores.insert("bob".to_string(), 7.0);
+        scores.insert("carol".to_string(), 7.0);
+        scores.insert("dave".to_string(), 1.0);
+
+        let best = lup!(Max<_, f32>: k of scores => {scores[&k]});
+        assert_eq!(best.value, 7.0);
+        assert_eq!(best.evidence, Some("bob".to_string()));
+
+        let worst = lup!(Min<_, f32>: k of scores => {scores[&k]});
+        assert_eq!(worst.evidence, Some("dave".to_string()));
+
+        let any = lup!(Any<_>: k of scores => {k.starts_with('c')});
+        assert_eq!(any.evidence, Some("carol".to_string()));
+
+        let all = lup!(All<_>: k of scores => {scores[&k] > 2.0});
+        assert_eq!(all.evidence, Some("dave".to_string()));
+    }
+
+    #[test]
+    fn try_sum() {
+        let words = ["1.5", "2", "x", "4"];
+        let mut ran = 0;
+        let sum = try_lup!(Sum<f32>: i by words => {
+            ran += 1;
+            words[i].parse::<f32>()
+        });
+        assert!(sum.is_err());
+        assert_eq!(ran, 3);
+
+        let sum: Result<f32, ::std::num::ParseFloatError> =
+            try_lup!(Sum<f32>: i by words[..2] => {words[i].parse()});
+        assert_eq!(sum, Ok(3.5));
+
+        let list = [["1", "2"], ["3", "-"]];
+        let mut ran = 0;
+        let sum = try_lup!(Sum<f32>: i, j by list => {
+            ran += 1;
+            list[i][j].parse::<f32>()
+        });
+        assert!(sum.is_err());
+        assert_eq!(ran, 4);
+    }
+
+    #[test]
+    fn sparse() {
+        let sparse: Vec<(usize, f32)> = vec![(3, 1.0), (10, 5.0), (42, 2.0)];
+
+        let max = lup!(Max<_, f32>: (i, v) in sparse.iter().cloned() => {v});
+        assert_eq!(max.value, 5.0);
+        assert_eq!(max.evidence, Some(10));
+
+        let any = lup!(Any<_>: (i, v) in sparse.iter().cloned() => {v < 2.0 && i > 0});
+        assert_eq!(any.evidence, Some(3));
+
+        let rows = [vec![(1, 2.0), (5, 4.0)], vec![(2, 8.0)]];
+        let max = lup!(Max<_, f32>:
+            i in 0..rows.len(),
+            (j, v) in rows[i].iter().cloned() => {v});
+        assert_eq!(max.evidence, Some((1, 2)));
+    }
+
+    #[test]
+    fn tiles() {
+        let (h, w) = (37, 23);
+        let img: Vec<f64> = lup!(Sift<_>: i in 0..h * w => {(i as f64 * 0.37).sin()});
+
+        let naive = lup!(Sum<f64>: i in 0..h, j in 0..w => {img[i * w + j]});
+        let tiled = lup!(Sum<f64>: i in 0..h, j in 0..w tiles (8, 5) => {img[i * w + j]});
+        assert!((naive - tiled).abs() < 1e-9);
+
+        let mut count = 0;
+        lup!(For: i in 0..h, j in 0..w tiles (32, 32) => {count += 1; let _ = (i, j);});
+        assert_eq!(count, h * w);
+
+        // Counts and integer sums are exact.
+        let naive = lup!(Sum<usize>: i in 0..h, j in 0..w => {let _ = (i, j); 1});
+        let tiled = lup!(Sum<usize>: i in 0..h, j in 0..w tiles (8, 5) => {let _ = (i, j); 1});
+        assert_eq!((naive, tiled), (h * w, h * w));
+        let pixel = |i: usize, j: usize| ((i * w + j) as u64 * 7919) % 1009;
+        let naive = lup!(Sum<u64>: i in 0..h, j in 0..w => {pixel(i, j)});
+        let tiled = lup!(Sum<u64>: i in 0..h, j in 0..w tiles (8, 5) => {pixel(i, j)});
+        assert_eq!(naive, tiled);
+        assert_eq!(naive, (0..(h * w) as u64).map(|k| k * 7919 % 1009).sum::<u64>());
+
+        let max = lup!(Max<_, f64>: i in 0..h, j in 0..w => {img[i * w + j]});
+        let tiled = lup!(Max<_, f64>: i in 0..h, j in 0..w tiles (4, 4) => {img[i * w + j]});
+        assert_eq!(max.value, tiled.value);
+        assert_eq!(max.evidence, tiled.evidence);
+
+        let any = lup!(Any<_>: i in 0..h, j in 0..w tiles (4, 4) => {img[i * w + j] > 0.999});
+        let (i, j) = any.evidence.unwrap();
+        assert!(img[i * w + j] > 0.999);
+
+        let order: Vec<_> = Tiles::new(0..3, 1..4, (2, 2)).collect();
+        assert_eq!(order, vec![
+            (0, 1), (0, 2), (1, 1), (1, 2),
+            (0, 3), (1, 3),
+            (2, 1), (2, 2),
+            (2, 3),
+        ]);
+        assert_eq!(Tiles::new(0..0, 0..4, (2, 2)).count(), 0);
+        assert_eq!(Tiles::new(0..4, 2..2, (2, 2)).count(), 0);
+    }
+
+    #[test]
+    fn window() {
+        let signal = [0.0, 1.0, 0.0, 3.0, 4.0, 2.0, 0.0, 1.0];
+        let peak = lup!(Max<_, f64>: w window 3 by signal => {
+            signal[w.start..w.end].iter().sum::<f64>()
+        });
+        assert_eq!(peak.value, 9.0);
+        assert_eq!(peak.evidence, Some(3));
+
+        let n = lup!(Sum<f64>: w window 8 by signal => {w.len() as f64});
+        assert_eq!(n, 8.0);
+
+        let short = [1.0, 2.0];
+        let peak = lup!(Max<_, f64>: w window 3 by short => {short[w].iter().sum::<f64>()});
+        assert!(peak.value.is_nan());
+        assert_eq!(peak.evidence, None);
+    }
+
+    #[test]
+    fn downto() {
+        let list = [1, 5, 2, 5, 3];
+        let n = list.len() - 1;
+        let last = lup!(Any<_>: i in n downto 0 => {list[i] == 5});
+        assert_eq!(last.evidence, Some(3));
+
+        let order = lup!(Sift<Vec<usize>>: i in n downto 1 => {i});
+        assert_eq!(order, vec![4, 3, 2, 1]);
+
+        let n = 0;
+        let mut count = 0;
+        let zero = lup!(Sift<Vec<usize>>: i in n downto 0 => {count += 1; i});
+        assert_eq!(zero, vec![0]);
+        assert_eq!(count, 1);
+
+        let (from, to) = (1, 2);
+        let empty = lup!(Sift<Vec<usize>>: i in from downto to => {i});
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn with() {
+        let xs = [1.0, 2.0, 3.0, 6.0];
+        let mean = lup!(Sum<f64>: i by xs => {xs[i]}) / xs.len() as f64;
+        let var = lup!(Sum<f64>: i by xs with d = xs[i] - mean => {d * d});
+        assert_eq!(var, 14.0);
+
+        let quad = lup!(Sum<f64>: i by xs with d = xs[i] - mean, d2 = d * d => {d2 * d2});
+        assert_eq!(quad, 98.0);
+
+        let m = [[1.0, 2.0], [3.0, 4.0]];
+        let max = lup!(Max<_, f64>: i, j by m with x = m[i][j], y = x * x - x => {y});
+        assert_eq!(max.value, 12.0);
+        assert_eq!(max.evidence, Some((1, 1)));
+
+        let max = lup!(Max<_, f64>: i in 0..2, j in 0..2 with x = m[i][j] => {-x});
+        assert_eq!(max.evidence, Some((0, 0)));
+
+        let n = xs.len() - 1;
+        let last = lup!(Sift<Vec<f64>>: i in n downto 2 with x = xs[i] => {x});
+        assert_eq!(last, vec![6.0, 3.0]);
+    }
+
+    #[test]
+    fn expr_body() {
+        let list = [vec![vec![1.0, 2.0, 3.0]]];
+
+        let sum = lup!(Sum<f32>:
+            i in 0..list.len(),
+            j in 0..list[i].len(),
+            k in 0..list[i][j].len() => list[i][j][k]);
+        assert_eq!(sum, 6.0);
+
+        let any = lup!(Any<_>: i, j, k by list => list[i][j][k] > 2.0);
+        assert_eq!(any.evidence, Some((0, 0, 2)));
+
+        let max = lup!(Max<_, f32>: i by list[0][0] => list[0][0][i]);
+        assert_eq!(max.evidence, Some(2));
+
+        let xs = [1.0, 2.0, 3.0, 6.0];
+        let var = lup!(Sum<f64>: i by xs with d = xs[i] - 3.0 => d * d);
+        assert_eq!(var, 14.0);
+
+        let last = lup!(Sift<Vec<f64>>: i in 3 downto 2 => xs[i]);
+        assert_eq!(last, vec![6.0, 3.0]);
+    }
+
+    #[test]
+    fn const_loop() {
+        const SQUARES: [f32; 16] = const_lup!(Vector<[f32; 16]>: i in 0..16 => {(i * i) as f32});
+        const TOTAL: f32 = const_lup!(Sum<f32>: i by SQUARES => {SQUARES[i]});
+        const COUNT: u32 = const_lup!(Sum<u32>: i in 0..10 => {if i % 3 == 0 {1} else {0}});
+        const SHIFTED: [u8; 4] = const_lup!(Vector<[u8; 4]>: i in 1..3 => {i as u8 + 10});
+
+        let squares: Vec<f32> = lup!(Sift<_>: i in 0..16 => {(i * i) as f32});
+        assert_eq!(&SQUARES[..], &squares[..]);
+        assert_eq!(TOTAL, lup!(Sum<f32>: i by squares => {squares[i]}));
+        assert_eq!(COUNT, 4);
+        assert_eq!(SHIFTED, [0, 11, 12, 0]);
+    }
+
+    #[test]
+    fn hygiene() {
+        let sum = [1.0, 2.0];
+        let iter = [3.0, 4.0];
+        let total = lup!(Sum<f64>: i by sum => {sum[i] * iter[i]});
+        assert_eq!(total, 11.0);
+
+        let total = lup!(Sum<f64>: i in 0..2 => {let sum = sum[i]; sum + iter[i]});
+        assert_eq!(total, 10.0);
+
+        const SUM: [f32; 2] = [1.0, 2.0];
+        const TOTAL: f32 = const_lup!(Sum<f32>: i by SUM => {SUM[i]});
+        assert_eq!(TOTAL, 3.0);
+    }
+
+    #[test]
+    fn by_offset() {
+        let data: Vec<f64> = (0..300).map(|i| ((i * 37) % 101) as f64).collect();
+        let part = &data[100..200];
+        let max = lup!(Max<_, f64>: i by part offset 100 => {part[i]});
+        let i = max.evidence.unwrap();
+        assert!((100..200).contains(&i));
+        assert_eq!(data[i], max.value);
+        let plain = lup!(Max<_, f64>: i by part => {part[i]});
+        assert_eq!(plain.evidence, Some(i - 100));
+
+        let res = lup!(Any<_>: i by data[150..] offset 150 => {data[150 + i] == 0.0});
+        assert_eq!(res.evidence, Some(202));
+        assert_eq!(data[202], 0.0);
+        let found = lup!(Sift<Vec<usize>>: i by data[..0] offset 7 => {i});
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn skip_take() {
+        let items: Vec<usize> = (0..25).collect();
+
+        let page = lup!(Sift<Vec<usize>>: i by items skip 10 take 5 => {items[i]});
+        assert_eq!(page, vec![10, 11, 12, 13, 14]);
+
+        let last = lup!(Sift<Vec<usize>>: i by items skip 20 take 10 => {items[i]});
+        assert_eq!(last, vec![20, 21, 22, 23, 24]);
+
+        let none = lup!(Sift<Vec<usize>>: i by items skip 30 take 10 => {items[i]});
+        assert!(none.is_empty());
+
+        let first = lup!(Sift<Vec<usize>>: i by items take 3 => {items[i]});
+        assert_eq!(first, vec![0, 1, 2]);
+
+        let offset = 22;
+        let tail = lup!(Sift<Vec<usize>>: i by items skip offset => items[i]);
+        assert_eq!(tail, vec![22, 23, 24]);
+
+        let odd = lup!(Any<_>: i by items skip 4 take (2 * 3) with x = items[i] => {x % 2 == 1});
+        assert_eq!(odd.evidence, Some(5));
+    }
+
+    #[test]
+    fn underscore() {
+        // Estimate pi by sampling points with a linear congruential generator.
+        let mut state: u32 = 12345;
+        let mut rand = || {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 8) as f64 / (1 << 24) as f64
+        };
+        let n = 10_000;
+        let inside = lup!(Sum<f64>: _ in 0..n => {
+            let (x, y) = (rand(), rand());
+            if x * x + y * y <= 1.0 {1.0} else {0.0}
+        });
+        let pi = 4.0 * inside / n as f64;
+        assert!((pi - ::std::f64::consts::PI).abs() < 0.05);
+
+        let list = [[1.0, 2.0], [3.0, 4.0]];
+        let mut count = 0;
+        let sum = lup!(Sum<f64>: _ by list => {count += 1; 1.0});
+        assert_eq!(sum, 2.0);
+        assert_eq!(count, 2);
+
+        let sum = lup!(Sum<f64>: _ in 0..2, j in 0..2 => list[1][j]);
+        assert_eq!(sum, 14.0);
+
+        let values = lup!(Sift<Vec<f64>>: (_, v) in list.iter().cloned().enumerate() => v[0]);
+        assert_eq!(values, vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn functions() {
+        let words = ["mary", "had", "a", "little", "lamb"];
+        let lamb = any(0..words.len(), |i| words[i] == "lamb");
+        assert!(lamb.value);
+        assert_eq!(lamb.evidence, Some(4));
+
+        let short = all(0..words.len(), |i| words[i].len() < 5);
+        assert_eq!(short.evidence, Some(3));
+
+        let arr = [[1, 2], [3, 4]];
+        let b = run::<_, _, Any<_>>(0..arr.len(), |i| any(0..arr[i].len(), |j| arr[i][j] > 2));
+        assert_eq!(b.evidence, Some((1, 0)));
+
+        let data = [(1, 1), (2, 2), (3, 4), (4, 4)];
+        let a = max(0..data.len(), |i| data[i].0 as f32);
+        let b = max(0..data.len(), |i| data[i].1 as f32);
+        assert_eq!((a.value, b.value), (4.0, 4.0));
+        assert_eq!((a.evidence, b.evidence), (Some(3), Some(2)));
+
+        let m = min(0..data.len(), |i| data[i].1 as f64);
+        assert_eq!(m.evidence, Some(0));
+
+        let total = sum(0..data.len(), |i| data[i].0 as f64);
+        assert_eq!(total, 10.0);
+
+        let data = [vec![1, 2, 6, 4, 5, 3], vec![4, 6, 9, 3, 2, 1]];
+        let search = run::<_, _, Any<_>>(0..data.len(), |i| {
+            max(0..data[i].len(), |j| data[i][j] as f32).le(&7.0)
+        });
+        assert_eq!(search.evidence, Some((0, 2)));
+    }
+
+    #[test]
+    fn iterator() {
+        let words = ["mary", "had", "a", "little", "lamb"];
+        let lamb = words.iter().map(|w| w == &"lamb").lup::<Any<usize>>();
+        assert!(lamb.value);
+        assert_eq!(lamb.evidence, Some(4));
+        assert_eq!(words[lamb.evidence.unwrap()], "lamb");
+
+        let missing = words.iter().map(|w| w.is_empty()).lup::<Any<usize>>();
+        assert_eq!(missing.evidence, None);
+
+        let total = words.iter().map(|w| w.len() as f64).lup::<Sum<f64>>();
+        assert_eq!(total, 18.0);
+
+        let longest = words.iter().map(|w| w.len() as f32).lup::<Max<usize, f32>>();
+        assert_eq!(longest.value, 6.0);
+        assert_eq!(longest.evidence, Some(3));
+
+        let evens = (1..10).filter(|x| x % 2 == 0).map(|x| x as f64).lup::<Sum<f64>>();
+        assert_eq!(evens, 20.0);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray() {
+        use ndarray::Array2;
+
+        let a = Array2::from_shape_vec((3, 4), vec![
+            0.5, 2.0, -1.0, 3.0,
+            7.0, 1.0, 0.0, 2.5,
+            4.0, 7.0, 6.0, -2.0,
+        ]).unwrap();
+
+        let max = lup!(Max<_, f64>: i, j by2 a => a[[i, j]]);
+        let argmax = a.indexed_iter()
+            .fold(None, |best: Option<((usize, usize), f64)>, (ind, &x)| match best {
+                Some((_, y)) if y >= x => best,
+                _ => Some((ind, x)),
+            }).unwrap();
+        assert_eq!(max.value, argmax.1);
+        assert_eq!(max.evidence, Some(argmax.0));
+
+        let negative = lup!(Any<_>: i, j by2 a.view() => a[[i, j]] < 0.0);
+        assert_eq!(negative.evidence, Some((0, 2)));
+
+        let sum = lup!(Sum<f64>: i, j by2 a => a[[i, j]]);
+        assert_eq!(sum, a.sum());
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn glam() {
+        use glam::{Vec3, DVec2};
+
+        let points = vec![
+            Vec3::new(1.0, -2.0, 0.5),
+            Vec3::new(-3.0, 4.0, 2.0),
+            Vec3::new(0.0, 1.0, -1.5),
+        ];
+        assert_eq!(lup!(Sum<Vec3>: i by points => {points[i]}), Vec3::new(-2.0, 3.0, 1.0));
+        let lo = lup!(Min<_, Vec3>: i by points => {points[i]});
+        let hi = lup!(Max<_, Vec3>: i by points => {points[i]});
+        assert_eq!(lo, Vec3::new(-3.0, -2.0, -1.5));
+        assert_eq!(hi, Vec3::new(1.0, 4.0, 2.0));
+        assert!(lup!(Max<_, Vec3>: i by points[..0] => {points[i]}).is_nan());
+
+        assert_eq!(lup!(Vector<Vec3>: i in 0..3 => {i as f32}), Vec3::new(0.0, 1.0, 2.0));
+        assert_eq!(lup!(Vector<DVec2>: i in 0..2 => {0.5 * i as f64}), DVec2::new(0.0, 0.5));
+    }
+
+    #[cfg(feature = "mint")]
+    #[test]
+    fn mint() {
+        use mint::{Vector2, Vector3};
+
+        let v: Vector3<f32> = lup!(Vector<[f32; 3]>: i in 0..3 => {i as f32 + 1.0}).into();
+        assert_eq!(v, Vector3 {x: 1.0, y: 2.0, z: 3.0});
+        let a: [f32; 3] = v.into();
+        assert_eq!(a, [1.0, 2.0, 3.0]);
+
+        assert_eq!(lup!(Vector<Vector3<f32>>: i in 0..3 => {i as f32 + 1.0}), v);
+        let vs = [Vector2 {x: 1.0, y: 2.0}, Vector2 {x: 3.0, y: -4.0}];
+        assert_eq!(lup!(Sum<Vector2<f64>>: i by vs => {vs[i]}), Vector2 {x: 4.0, y: -2.0});
+        assert_eq!(lup!(Prod<Vector2<f64>>: i by vs => {vs[i]}), Vector2 {x: 3.0, y: -8.0});
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra() {
+        use nalgebra::{Vector3, Vector4};
+
+        let v = lup!(Vector<Vector3<f64>>: i in 0..3 => {i as f64 + 1.0});
+        assert_eq!(v, Vector3::new(1.0, 2.0, 3.0));
+
+        let points: Vec<Vector3<f64>> = vec![
+            Vector3::new(1.0, 0.0, 2.0),
+            Vector3::new(0.5, 1.0, -1.0),
+            Vector3::new(-0.5, 2.0, 0.0),
+        ];
+        let sum = lup!(Sum<Vector3<f64>>: i by points => {points[i]});
+        assert_eq!(sum, Vector3::new(1.0, 3.0, 1.0));
+
+        let sum = lup!(Sum<Vector4<f32>>: i in 0..4 => {Vector4::from_element(i as f32)});
+        assert_eq!(sum, Vector4::from_element(6.0));
+    }
+
+    #[cfg(feature = "vecmath")]
+    #[test]
+    fn vecmath() {
+        use vecmath::{Matrix4, row_mat4_transform};
+
+        fn translation(x: f32, y: f32, z: f32) -> Matrix4<f32> {
+            [
+                [1.0, 0.0, 0.0, x],
+                [0.0, 1.0, 0.0, y],
+                [0.0, 0.0, 1.0, z],
+                [0.0, 0.0, 0.0, 1.0],
+            ]
+        }
+
+        let steps = [[1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.5, 0.0, -3.0]];
+        let m = lup!(MatMulProd<f32>: i by steps => {
+            translation(steps[i][0], steps[i][1], steps[i][2])
+        });
+        assert_eq!(m, translation(1.5, 2.0, -3.0));
+        assert_eq!(row_mat4_transform(m, [1.0, 1.0, 1.0, 1.0]), [2.5, 3.0, -2.0, 1.0]);
+
+        let scale = [
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let list = [translation(1.0, 0.0, 0.0), scale];
+        let m = lup!(MatMulProd<f32>: i by list => {list[i]});
+        assert_eq!(row_mat4_transform(m, [1.0, 1.0, 1.0, 1.0]), [3.0, 2.0, 2.0, 1.0]);
+
+        let id = lup!(MatMulProd<f64>: _i in 0..0 => {[[0.0; 4]; 4]});
+        assert_eq!(id, vecmath::mat4_id());
+
+        let sum = lup!(Sum<Matrix4<f32>>: i in 0..3 => {translation(i as f32, 0.0, 0.0)});
+        assert_eq!(sum[0], [3.0, 0.0, 0.0, 3.0]);
+        assert_eq!(sum[3], [0.0, 0.0, 0.0, 3.0]);
+
+        let v: Matrix4<f32> = lup!(Vector<Matrix4<f32>>: i in 0..4 => {[i as f32; 4]});
+        assert_eq!(v[2], [2.0; 4]);
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half() {
+        use half::f16;
+
+        let data: Vec<f16> = [0.5, -2.0, 3.25, 3.25, 1.0].iter().map(|&x| f16::from_f32(x)).collect();
+        let max = lup!(Max<_, f16>: i by data => {data[i]});
+        assert_eq!(max.value, f16::from_f32(3.25));
+        assert_eq!(max.evidence, Some(2));
+        let min = lup!(Min<_, f16>: i by data => {data[i]});
+        assert_eq!(min.value, f16::from_f32(-2.0));
+        assert_eq!(min.evidence, Some(1));
+
+        let empty: Vec<f16> = vec![];
+        let max = lup!(Max<_, f16>: i by empty => {empty[i]});
+        assert_eq!(max.evidence, None);
+
+        // Accumulating in `f16` would get stuck at 2048.
+        let ones = vec![f16::ONE; 4096];
+        let sum = lup!(HalfSum: i by ones => {ones[i]});
+        assert_eq!(sum, f16::from_f32(4096.0));
+        let mut naive = f16::ZERO;
+        for &x in &ones {naive += x}
+        assert_eq!(naive, f16::from_f32(2048.0));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint() {
+        use num_bigint::{BigInt, BigUint};
+
+        let fact = lup!(Prod<BigUint>: i in 1..51 => {BigUint::from(i as u64)});
+        let expected: BigUint = "30414093201713378043612608166064768844377641568960512000000000000"
+            .parse().unwrap();
+        assert_eq!(fact, expected);
+
+        // Primitive values are promoted.
+        let fact = lup!(Prod<BigUint>: i in 1..51 => {i as u64});
+        assert_eq!(fact, expected);
+
+        let sum = lup!(Sum<BigUint>: _i in 0..4 => {u128::MAX});
+        assert_eq!(sum, BigUint::from(u128::MAX) * 4u8);
+
+        let sum = lup!(Sum<BigInt>: i in 0..10 => {if i % 2 == 0 {i as i64} else {-(i as i64)}});
+        assert_eq!(sum, BigInt::from(-5));
+
+        let empty = lup!(Prod<BigInt>: i in 0..0 => {BigInt::from(i)});
+        assert_eq!(empty, BigInt::from(1));
+    }
+
+    #[test]
+    fn scalar() {
+        // Q16.16 fixed point number.
+        #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+        struct Fixed(i32);
+
+        impl Fixed {
+            fn from_f32(x: f32) -> Fixed {Fixed((x * 65536.0) as i32)}
+        }
+
+        impl Scalar for Fixed {
+            fn zero() -> Fixed {Fixed(0)}
+            fn one() -> Fixed {Fixed(1 << 16)}
+            fn add(self, other: Fixed) -> Fixed {Fixed(self.0 + other.0)}
+            fn mul(self, other: Fixed) -> Fixed {Fixed(((self.0 as i64 * other.0 as i64) >> 16) as i32)}
+            fn invalid() -> Fixed {Fixed(i32::MIN)}
+            fn is_invalid(self) -> bool {self.0 == i32::MIN}
+        }
+
+        let data = [Fixed::from_f32(1.5), Fixed::from_f32(-2.0), Fixed::from_f32(0.25)];
+        let max = lup!(Max<_, Fixed>: i by data => {data[i]});
+        assert_eq!(max.value, Fixed::from_f32(1.5));
+        assert_eq!(max.evidence, Some(0));
+        let min = lup!(Min<_, Fixed>: i by data => {data[i]});
+        assert_eq!(min.evidence, Some(1));
+        let sum = lup!(Sum<Fixed>: i by data => {data[i]});
+        assert_eq!(sum, Fixed::from_f32(-0.25));
+        let prod = lup!(Prod<Fixed>: i by data => {data[i]});
+        assert_eq!(prod, Fixed::from_f32(-0.75));
+        let sum = lup!(Sum<[Fixed; 2]>: i by data => {[data[i], Fixed::one()]});
+        assert_eq!(sum, [Fixed::from_f32(-0.25), Fixed::from_f32(3.0)]);
+
+        let list = [[Fixed::from_f32(1.0), Fixed::from_f32(3.0)], [Fixed::from_f32(2.0), Fixed::zero()]];
+        let max = lup!(Max<_, Fixed>: i, j by list => {list[i][j]});
+        assert_eq!(max.evidence, Some((0, 1)));
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn secret_arbitrary(s in proptest::arbitrary::any::<Secret<usize, bool>>()) {
+            let neg = !s.clone();
+            proptest::prop_assert_eq!(neg.value, !s.value);
+            proptest::prop_assert_eq!(neg.evidence, s.evidence);
+        }
+
+        #[test]
+        fn sum_permutation(
+            (list, perm) in proptest::collection::vec(-1000i64..1000, 0..20)
+                .prop_flat_map(|list| {
+                    let perm = proptest::strategy::Just(list.clone()).prop_shuffle();
+                    (proptest::strategy::Just(list), perm)
+                })
+        ) {
+            let a = lup!(Sum<f64>: i by list => {list[i] as f64});
+            let b = lup!(Sum<f64>: i by perm => {perm[i] as f64});
+            proptest::prop_assert_eq!(a, b);
+        }
+
+        #[test]
+        fn hash_collectors_deterministic(
+            (list, perm) in proptest::collection::vec(0u8..20, 0..40)
+                .prop_flat_map(|list| {
+                    let perm = proptest::strategy::Just(list.clone()).prop_shuffle();
+                    (proptest::strategy::Just(list), perm)
+                })
+        ) {
+            use std::collections::hash_map::DefaultHasher;
+            use std::hash::{BuildHasher, Hasher};
+
+            #[derive(Default)]
+            struct Seeded<const SEED: u64>;
+            impl<const SEED: u64> BuildHasher for Seeded<SEED> {
+                type Hasher = DefaultHasher;
+                fn build_hasher(&self) -> DefaultHasher {
+                    let mut h = DefaultHasher::new();
+                    h.write_u64(SEED);
+                    h
+                }
+            }
+
+            let a = lup!(Frequencies<u8, Seeded<1>>: i by list => {list[i]});
+            let b = lup!(Frequencies<u8, Seeded<2>>: i by perm => {perm[i]});
+            let c = lup!(Frequencies<u8>: i by perm => {perm[i]});
+            let sorted = lup!(SortedFrequencies<u8>: i by list => {list[i]});
+            proptest::prop_assert_eq!(a.len(), sorted.len());
+            for (k, n) in &sorted {
+                proptest::prop_assert_eq!(a[k], *n);
+                proptest::prop_assert_eq!(b[k], *n);
+                proptest::prop_assert_eq!(c[k], *n);
+            }
+            let order: Vec<u8> = a.keys().cloned().collect();
+            let mut first: Vec<u8> = vec![];
+            for &k in &list {
+                if !first.contains(&k) {first.push(k)}
+            }
+            proptest::prop_assert_eq!(&order, &first);
+            let a2 = lup!(Frequencies<u8, Seeded<2>>: i by list => {list[i]});
+            let a3 = lup!(Frequencies<u8>: i by list => {list[i]});
+            proptest::prop_assert!(a.iter().eq(a2.iter()));
+            proptest::prop_assert!(a2.into_iter().eq(a3));
+
+            let a = lup!(IsUnique<u8, Seeded<1>>: i by list => {list[i]});
+            let b = lup!(IsUnique<u8, Seeded<2>>: i by list => {list[i]});
+            let c = lup!(IsUnique<u8>: i by list => {list[i]});
+            let d = lup!(IsUnique<u8>: i by perm => {perm[i]});
+            proptest::prop_assert_eq!((a.value, a.evidence), (b.value, b.evidence));
+            proptest::prop_assert_eq!((a.value, a.evidence), (c.value, c.evidence));
+            proptest::prop_assert_eq!(a.value, d.value);
+        }
+
+        #[test]
+        fn any_laws(
+            data in proptest::collection::vec(proptest::collection::vec(-10.0f64..10.0, 0..6), 0..6),
+            limit in -10.0f64..10.0
+        ) {
+            let res = lup!(Any<_>: i, j by data => {data[i][j] > limit});
+            proptest::prop_assert_eq!(res.value, data.iter().flatten().any(|&x| x > limit));
+            if let Some((i, j)) = res.evidence {
+                proptest::prop_assert!(data[i][j] > limit);
+            }
+            proptest::prop_assert_eq!(res.value, res.evidence.is_some());
+        }
+
+        #[test]
+        fn all_laws(
+            data in proptest::collection::vec(proptest::collection::vec(-10.0f64..10.0, 0..6), 0..6),
+            limit in -10.0f64..10.0
+        ) {
+            let res = lup!(All<_>: i, j by data => {data[i][j] > limit});
+            proptest::prop_assert_eq!(res.value, data.iter().flatten().all(|&x| x > limit));
+            if let Some((i, j)) = res.evidence {
+                proptest::prop_assert!(data[i][j] <= limit);
+            }
+            proptest::prop_assert_eq!(res.value, res.evidence.is_none());
+        }
+
+        #[test]
+        fn max_min_laws(
+            data in proptest::collection::vec(proptest::collection::vec(-1e6f64..1e6, 0..6), 0..6)
+        ) {
+            let max = lup!(Max<_, _>: i, j by data => {data[i][j]});
+            let min = lup!(Min<_, _>: i, j by data => {data[i][j]});
+            let empty = data.iter().all(|row| row.is_empty());
+            proptest::prop_assert_eq!(max.evidence.is_none(), empty);
+            proptest::prop_assert_eq!(min.evidence.is_none(), empty);
+            for &x in data.iter().flatten() {
+                proptest::prop_assert!(max.value >= x);
+                proptest::prop_assert!(min.value <= x);
+            }
+            if let Some((i, j)) = max.evidence {
+                proptest::prop_assert_eq!(data[i][j], max.value);
+            }
+            if let Some((i, j)) = min.evidence {
+                proptest::prop_assert_eq!(data[i][j], min.value);
+            }
+        }
+
+        #[test]
+        fn sum_nested(
+            data in proptest::collection::vec(proptest::collection::vec(-1000i32..1000, 0..6), 0..6)
+        ) {
+            let sum = lup!(Sum<f64>: i, j by data => {data[i][j] as f64});
+            let expected: i32 = data.iter().flatten().sum();
+            proptest::prop_assert_eq!(sum, expected as f64);
+        }
+    }
+
+    #[test]
+    fn for_loop() {
+        let list = vec![vec![1, 2], vec![3, 4]];
+        lup!(For: i, j by list => {
+            println!("{}", list[i][j]);
+        });
+    }
+}
+
+#[cfg(all(test, not(feature = "std")))]
+mod no_std_tests {
+    use super::*;
+
+    #[test]
+    fn core_loops() {
+        let list = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+
+        assert_eq!(lup!(Sum<f64>: i, j by list => list[i][j]), 21.0);
+        assert_eq!(lup!(Prod<f64>: i, j by list => list[i][j]), 720.0);
+        assert_eq!(lup!(Any<_>: i, j by list => list[i][j] > 4.0).evidence, Some((1, 1)));
+        assert_eq!(lup!(All<_>: i, j by list => list[i][j] < 3.0).evidence, Some((0, 2)));
+        assert_eq!(lup!(Max<_, f64>: i, j by list => list[i][j]).evidence, Some((1, 2)));
+        assert_eq!(lup!(Min<_, f64>: i, j by list => list[i][j]).evidence, Some((0, 0)));
+        assert_eq!(lup!(Vector<[f64; 3]>: i by list[1] => list[1][i] * 2.0), [8.0, 10.0, 12.0]);
+        assert_eq!(any(0..3, |i| list[0][i] == 2.0).evidence, Some(1));
+        assert_eq!(list[1].iter().cloned().lup::<Sum<f64>>(), 15.0);
     }
 }
@@ -0,0 +1,34 @@
+use *;
+use core::fmt::{Display, Write};
+use alloc::string::String;
+
+/// A loop that links items together into text.
+///
+/// Every item implementing `Display` can be used,
+/// so numbers, strings and characters can be mixed in the same loop.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Link;
+///
+/// fn main() {
+///     let words = ["a", "b", "c"];
+///     let text = lup!(Link: i by words => {words[i]});
+///     println!("{}", text); // Prints `abc`.
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Link(pub String);
+
+impl<T: Display> Lup<usize, T> for Link {
+    type Inner = String;
+
+    fn start() -> Self {Link(String::new())}
+    fn it(&mut self, _ind: usize, val: T) -> bool {
+        write!(&mut self.0, "{}", val).unwrap();
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
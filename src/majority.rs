@@ -0,0 +1,53 @@
+use *;
+
+/// Majority vote loop.
+///
+/// Finds the value that occurs in more than half of the items,
+/// using the Boyer-Moore majority vote algorithm in constant memory.
+///
+/// If there is a majority, the result is the majority value.
+/// If there is none, the result is an arbitrary value that occurred in the loop,
+/// since a single pass can not tell the difference.
+/// When it is not known whether there is a majority,
+/// count the result with a second loop:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::{Majority, Sum};
+///
+/// fn main() {
+///     let votes = ['a', 'b', 'a', 'c', 'b'];
+///     let candidate = lup!(Majority<_>: i by votes => {votes[i]}).unwrap();
+///     let count = lup!(Sum<f64>: i by votes => {
+///         if votes[i] == candidate {1.0} else {0.0}
+///     });
+///     println!("{}", 2.0 * count > votes.len() as f64); // Prints `false`.
+/// }
+/// ```
+///
+/// Returns `None` when there are no items.
+#[derive(Debug, Clone)]
+pub struct Majority<T>(pub Option<T>, pub usize);
+
+impl<T> Default for Majority<T> {
+    fn default() -> Majority<T> {Majority(None, 0)}
+}
+
+impl<I, T: Eq> Lup<I, T> for Majority<T> {
+    type Inner = Option<T>;
+    fn start() -> Self {Majority::default()}
+    fn it(&mut self, _ind: I, val: T) -> bool {
+        if self.1 == 0 {
+            self.0 = Some(val);
+            self.1 = 1;
+        } else if self.0.as_ref() == Some(&val) {
+            self.1 += 1;
+        } else {
+            self.1 -= 1;
+        }
+        true
+    }
+    fn unwrap(self) -> Option<T> {self.0}
+}
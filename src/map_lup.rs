@@ -0,0 +1,62 @@
+use *;
+
+/// A function on body values, used by `MapLup`.
+///
+/// Implemented by zero-sized marker types,
+/// so the mapped loop can be started without arguments.
+pub trait MapFn<T> {
+    /// The type of the mapped value.
+    type Output;
+    /// Maps a value.
+    fn map(val: T) -> Self::Output;
+}
+
+/// Maps values to their absolute value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Abs;
+
+/// Maps values to their square.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sq;
+
+/// Maps values to their reciprocal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Recip;
+
+macro_rules! map_fn_impl {
+    ($f32:ty) => {
+        impl MapFn<$f32> for Abs {
+            type Output = $f32;
+            fn map(val: $f32) -> $f32 {if val < 0.0 {-val} else {val}}
+        }
+
+        impl MapFn<$f32> for Sq {
+            type Output = $f32;
+            fn map(val: $f32) -> $f32 {val * val}
+        }
+
+        impl MapFn<$f32> for Recip {
+            type Output = $f32;
+            fn map(val: $f32) -> $f32 {1.0 / val}
+        }
+    }
+}
+
+map_fn_impl!{f32}
+
+map_fn_impl!{f64}
+
+/// Loop that maps the body values before passing them to an inner loop.
+///
+/// For example, `MapLup<Max<_, f64>, Abs>` finds the maximum absolute value.
+#[derive(Debug, Clone)]
+pub struct MapLup<L, F>(pub L, pub PhantomData<F>);
+
+impl<I, T, F: MapFn<T>, L: Lup<I, F::Output>> Lup<I, T> for MapLup<L, F> {
+    type Inner = L::Inner;
+    fn start() -> Self {MapLup(L::start(), PhantomData)}
+    fn start_sized(hint: Option<usize>) -> Self {MapLup(L::start_sized(hint), PhantomData)}
+    fn it(&mut self, ind: I, val: T) -> bool {self.0.it(ind, F::map(val))}
+    fn unwrap(self) -> Self::Inner {self.0.unwrap()}
+    fn finish(self, iterations: usize) -> Self::Inner {self.0.finish(iterations)}
+}
@@ -0,0 +1,58 @@
+use *;
+
+/// Matrix multiplication loop for 4x4 transforms.
+///
+/// Composes 4x4 row major matrices `[[T; 4]; 4]`, starting with the identity matrix,
+/// without depending on a math library.
+/// The product is taken in loop order, `m0 * m1 * ... * mn`.
+/// With column vectors, i.e. the translation in the last column,
+/// this means that a point is transformed by the last matrix first.
+/// For example, looping over the local transforms of a node path
+/// from the root to a leaf gives the world transform of the leaf.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::MatMul;
+///
+/// fn main() {
+///     let offsets = [1.0, 2.0, 3.0];
+///     let m = lup!(MatMul: i by offsets => {
+///         [
+///             [1.0, 0.0, 0.0, offsets[i]],
+///             [0.0, 1.0, 0.0, 0.0],
+///             [0.0, 0.0, 1.0, 0.0],
+///             [0.0, 0.0, 0.0, 1.0],
+///         ]
+///     });
+///     println!("{:?}", m[0]); // Prints `[1.0, 0.0, 0.0, 6.0]`.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MatMul<T = f32>(pub [[T; 4]; 4]);
+
+macro_rules! mat_mul_impl {
+    ($($f32:ty),*) => {$(
+        impl<I> Lup<I, [[$f32; 4]; 4]> for MatMul<$f32> {
+            type Inner = [[$f32; 4]; 4];
+            fn start() -> Self {
+                let mut id = [[0.0; 4]; 4];
+                for (i, row) in id.iter_mut().enumerate() {row[i] = 1.0}
+                MatMul(id)
+            }
+            fn it(&mut self, _ind: I, val: [[$f32; 4]; 4]) -> bool {
+                let a = self.0;
+                for (i, row) in self.0.iter_mut().enumerate() {
+                    for (j, x) in row.iter_mut().enumerate() {
+                        *x = (0..4).map(|k| a[i][k] * val[k][j]).sum();
+                    }
+                }
+                true
+            }
+            fn unwrap(self) -> [[$f32; 4]; 4] {self.0}
+        }
+    )*}
+}
+
+mat_mul_impl!{f32, f64}
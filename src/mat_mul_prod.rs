@@ -0,0 +1,53 @@
+use *;
+use vecmath::{Matrix4, mat4_id, row_mat4_mul};
+
+/// Matrix product loop.
+///
+/// Composes 4x4 row major matrices with `vecmath::row_mat4_mul`,
+/// starting with the identity matrix.
+/// The product is taken in loop order, `m0 * m1 * ... * mn`,
+/// so when transforming a vector with `row_mat4_transform`
+/// the matrix of the last iteration is applied first.
+///
+/// Unlike `Prod`, which multiplies elementwise,
+/// this loop uses matrix multiplication.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::MatMulProd;
+///
+/// fn main() {
+///     let offsets = [1.0, 2.0, 3.0];
+///     let m = lup!(MatMulProd<f32>: i by offsets => {
+///         [
+///             [1.0, 0.0, 0.0, offsets[i]],
+///             [0.0, 1.0, 0.0, 0.0],
+///             [0.0, 0.0, 1.0, 0.0],
+///             [0.0, 0.0, 0.0, 1.0],
+///         ]
+///     });
+///     println!("{:?}", m[0]); // Prints `[1.0, 0.0, 0.0, 6.0]`.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MatMulProd<T>(pub Matrix4<T>);
+
+macro_rules! mat_mul_prod_impl {
+    ($f32:ty) => {
+        impl<I> Lup<I, Matrix4<$f32>> for MatMulProd<$f32> {
+            type Inner = Matrix4<$f32>;
+            fn start() -> Self {MatMulProd(mat4_id())}
+            fn it(&mut self, _ind: I, val: Matrix4<$f32>) -> bool {
+                self.0 = row_mat4_mul(self.0, val);
+                true
+            }
+            fn unwrap(self) -> Matrix4<$f32> {self.0}
+        }
+    }
+}
+
+mat_mul_prod_impl!{f32}
+
+mat_mul_prod_impl!{f64}
@@ -1,52 +1,155 @@
 use *;
+#[cfg(feature = "half")]
+use half::f16;
+#[cfg(feature = "glam")]
+use glam::{Vec2, Vec3, Vec4, DVec2, DVec3, DVec4};
 
 /// Maximum loop.
+#[derive(Debug, Clone)]
 pub struct Max<I, T>(pub Secret<I, T>);
 
+impl<K, S: Scalar> Lup<K, S> for Max<K, S> {
+    type Inner = Secret<K, S>;
+    fn start() -> Self {Max(Secret {evidence: None, value: S::invalid()})}
+    fn it(&mut self, ind: K, val: S) -> bool {
+        if self.0.value.is_invalid() || val > self.0.value {
+            self.0 = Secret {evidence: Some(ind), value: val};
+        }
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
 macro_rules! max_impl{
-    ($usize:ty , $f32:ty , $nan:expr) => {
-        impl Lup<$usize, $f32> for Max<$usize, $f32> {
-            type Inner = Secret<$usize, $f32>;
-            fn start() -> Self {Max(Secret {evidence: None, value: $nan})}
-            fn it(&mut self, ind: $usize, val: $f32) -> bool {
-                if self.0.value.is_nan() || val > self.0.value {
-                    self.0 = Secret {evidence: Some(ind), value: val};
+    ($usize:ty) => {
+        impl<S: Scalar> Lup<$usize, Secret<$usize, S>> for Max<($usize, $usize), S> {
+            type Inner = Secret<($usize, $usize), S>;
+            fn start() -> Self {Max(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<$usize, S>) -> bool {
+                if self.0.value.is_invalid() || val.value > self.0.value {
+                    self.0 = Secret {evidence: val.evidence.map(|a| (ind, a)), value: val.value};
                 }
                 true
             }
             fn unwrap(self) -> Self::Inner {self.0}
         }
 
-        impl Lup<$usize, Secret<$usize, $f32>> for Max<($usize, $usize), $f32> {
-            type Inner = Secret<($usize, $usize), $f32>;
-            fn start() -> Self {Max(Secret {evidence: None, value: $nan})}
-            fn it(&mut self, ind: $usize, val: Secret<$usize, $f32>) -> bool {
-                if self.0.value.is_nan() || val.value > self.0.value {
-                    if let Some(ind2) = val.evidence {
-                        self.0 = Secret {evidence: Some((ind, ind2)), value: val.value}
-                    }
+        impl<K: Key, S: Scalar> Lup<$usize, Secret<K, S>> for Max<($usize, K), S> {
+            type Inner = Secret<($usize, K), S>;
+            fn start() -> Self {Max(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<K, S>) -> bool {
+                if self.0.value.is_invalid() || val.value > self.0.value {
+                    self.0 = Secret {evidence: val.evidence.map(|key| (ind, key)), value: val.value};
                 }
                 true
             }
             fn unwrap(self) -> Self::Inner {self.0}
         }
 
-        impl Lup<$usize, Secret<($usize, $usize), $f32>> for Max<($usize, $usize, $usize), $f32> {
-            type Inner = Secret<($usize, $usize, $usize), $f32>;
-            fn start() -> Self {Max(Secret {evidence: None, value: $nan})}
-            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize), $f32>) -> bool {
-                if self.0.value.is_nan() || val.value > self.0.value {
-                    if let Some((a, b)) = val.evidence {
-                        self.0 = Secret {evidence: Some((ind, a, b)), value: val.value}
-                    }
+        impl<S: Scalar> Lup<$usize, Secret<($usize, $usize), S>> for Max<($usize, $usize, $usize), S> {
+            type Inner = Secret<($usize, $usize, $usize), S>;
+            fn start() -> Self {Max(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize), S>) -> bool {
+                if self.0.value.is_invalid() || val.value > self.0.value {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b)| (ind, a, b)),
+                        value: val.value
+                    };
                 }
                 true
             }
             fn unwrap(self) -> Self::Inner {self.0}
         }
+
+        impl<S: Scalar> Lup<$usize, Secret<($usize, $usize, $usize), S>>
+        for Max<($usize, $usize, $usize, $usize), S> {
+            type Inner = Secret<($usize, $usize, $usize, $usize), S>;
+            fn start() -> Self {Max(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize, $usize), S>) -> bool {
+                if self.0.value.is_invalid() || val.value > self.0.value {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b, c)| (ind, a, b, c)),
+                        value: val.value
+                    };
+                }
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    }
+}
+
+max_impl!{usize}
+
+max_impl!{u32}
+
+max_impl!{u16}
+
+/// Maximum loop over optional values.
+///
+/// Skips `None` values, which do not count as evidence.
+/// When all values are `None`, there is no evidence.
+#[derive(Debug, Clone)]
+pub struct MaxOpt<I, T>(pub Max<I, T>);
+
+impl<K, S: Scalar> Lup<K, Option<S>> for MaxOpt<K, S> {
+    type Inner = Secret<K, S>;
+    fn start() -> Self {MaxOpt(Max::start())}
+    fn it(&mut self, ind: K, val: Option<S>) -> bool {
+        match val {
+            Some(val) => self.0.it(ind, val),
+            None => true,
+        }
+    }
+    fn unwrap(self) -> Self::Inner {self.0.unwrap()}
+}
+
+/// Skips inner loops with `None` value, when nesting loops over `Option` values.
+impl<I, E, K, S: Scalar> Lup<I, Secret<E, Option<S>>> for MaxOpt<K, S>
+    where Max<K, S>: Lup<I, Secret<E, S>>
+{
+    type Inner = <Max<K, S> as Lup<I, Secret<E, S>>>::Inner;
+    fn start() -> Self {MaxOpt(<Max<K, S> as Lup<I, Secret<E, S>>>::start())}
+    fn it(&mut self, ind: I, val: Secret<E, Option<S>>) -> bool {
+        match val.value {
+            Some(value) => self.0.it(ind, Secret {evidence: val.evidence, value}),
+            None => true,
+        }
     }
+    fn unwrap(self) -> Self::Inner {<Max<K, S> as Lup<I, Secret<E, S>>>::unwrap(self.0)}
 }
 
-max_impl!{usize, f32, ::std::f32::NAN}
+/// Uses the evidence instead of a NaN sentinel to detect the first item.
+#[cfg(feature = "half")]
+impl<K> Lup<K, f16> for Max<K, f16> {
+    type Inner = Secret<K, f16>;
+    fn start() -> Self {Max(Secret {evidence: None, value: f16::NAN})}
+    fn it(&mut self, ind: K, val: f16) -> bool {
+        if self.0.evidence.is_none() || val > self.0.value {
+            self.0 = Secret {evidence: Some(ind), value: val};
+        }
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+#[cfg(feature = "glam")]
+macro_rules! max_glam_impl {
+    ($($vec:ident),*) => {$(
+        /// Componentwise maximum, without evidence.
+        ///
+        /// Returns NaN components when there are no values.
+        impl<K> Lup<K, $vec> for Max<K, $vec> {
+            type Inner = $vec;
+            fn start() -> Self {Max(Secret {evidence: None, value: $vec::NAN})}
+            fn it(&mut self, _ind: K, val: $vec) -> bool {
+                self.0.value = if self.0.value.is_nan_mask().any() {val} else {self.0.value.max(val)};
+                true
+            }
+            fn unwrap(self) -> $vec {self.0.value}
+        }
+    )*}
+}
 
-max_impl!{usize, f64, ::std::f64::NAN}
+#[cfg(feature = "glam")]
+max_glam_impl!{Vec2, Vec3, Vec4, DVec2, DVec3, DVec4}
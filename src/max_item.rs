@@ -0,0 +1,40 @@
+use *;
+
+/// Maximum loop that keeps the item with the maximum key.
+///
+/// The body returns `(key, item)`.
+/// The value of the resulting secret is `Some((key, item))` with the maximum key,
+/// or `None` if there were no items.
+/// The item is owned by the result,
+/// so it outlives the collection it was cloned from.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::MaxItem;
+///
+/// fn main() {
+///     let words = vec!["mary", "had", "a", "little", "lamb"];
+///     let res = lup!(MaxItem<_, _>: i by words => {(words[i].len() as f64, words[i])});
+///     println!("{:?}", res.value); // Prints `Some((6.0, "little"))`.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaxItem<I, T>(pub Secret<I, Option<(f64, T)>>);
+
+impl<K, T> Lup<K, (f64, T)> for MaxItem<K, T> {
+    type Inner = Secret<K, Option<(f64, T)>>;
+    fn start() -> Self {MaxItem(Secret {evidence: None, value: None})}
+    fn it(&mut self, ind: K, (key, item): (f64, T)) -> bool {
+        let better = match self.0.value {
+            None => true,
+            Some((best, _)) => best.is_nan() || key > best,
+        };
+        if better {
+            self.0 = Secret {evidence: Some(ind), value: Some((key, item))};
+        }
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
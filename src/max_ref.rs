@@ -0,0 +1,54 @@
+use *;
+
+/// Maximum loop that borrows the maximum item.
+///
+/// The body returns a reference to an item, usually `&list[i]` with `i by list`,
+/// and the items are compared with `PartialOrd`.
+/// The value of the resulting secret is the reference to the first maximum item,
+/// or `None` if there were no items, so large items are never cloned.
+/// To compare by a key, use `MaxItem` with a reference as item.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::MaxRef;
+///
+/// fn main() {
+///     let words = vec![String::from("mary"), String::from("had"), String::from("lamb")];
+///     let res = lup!(MaxRef<_>: i by words => {&words[i]});
+///     println!("{:?}", res.value); // Prints `Some("mary")`.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaxRef<'a, T: 'a>(pub Secret<usize, Option<&'a T>>);
+
+/// Minimum loop that borrows the minimum item.
+///
+/// Like `MaxRef`, but for the first minimum item.
+#[derive(Debug, Clone)]
+pub struct MinRef<'a, T: 'a>(pub Secret<usize, Option<&'a T>>);
+
+macro_rules! ref_impl {
+    ($name:ident, $op:tt) => {
+        impl<'a, T: PartialOrd> Lup<usize, &'a T> for $name<'a, T> {
+            type Inner = Secret<usize, Option<&'a T>>;
+            fn start() -> Self {$name(Secret {evidence: None, value: None})}
+            fn it(&mut self, ind: usize, val: &'a T) -> bool {
+                let better = match self.0.value {
+                    None => true,
+                    Some(best) => val $op best,
+                };
+                if better {
+                    self.0 = Secret {evidence: Some(ind), value: Some(val)};
+                }
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    }
+}
+
+ref_impl!{MaxRef, >}
+
+ref_impl!{MinRef, <}
@@ -1,44 +1,76 @@
 use *;
+#[cfg(feature = "half")]
+use half::f16;
+#[cfg(feature = "glam")]
+use glam::{Vec2, Vec3, Vec4, DVec2, DVec3, DVec4};
 
 /// Minimum loop.
+#[derive(Debug, Clone)]
 pub struct Min<I, T>(pub Secret<I, T>);
 
+impl<K, S: Scalar> Lup<K, S> for Min<K, S> {
+    type Inner = Secret<K, S>;
+    fn start() -> Self {Min(Secret {evidence: None, value: S::invalid()})}
+    fn it(&mut self, ind: K, val: S) -> bool {
+        if self.0.value.is_invalid() || val < self.0.value {
+            self.0 = Secret {evidence: Some(ind), value: val};
+        }
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
 macro_rules! min_impl {
-    ($usize:ty , $f32:ty , $nan:expr) => {
-        impl Lup<$usize, $f32> for Min<$usize, $f32> {
-            type Inner = Secret<$usize, $f32>;
-            fn start() -> Self {Min(Secret {evidence: None, value: $nan})}
-            fn it(&mut self, ind: $usize, val: $f32) -> bool {
-                if self.0.value.is_nan() || val < self.0.value {
-                    self.0 = Secret {evidence: Some(ind), value: val};
+    ($usize:ty) => {
+        impl<S: Scalar> Lup<$usize, Secret<$usize, S>> for Min<($usize, $usize), S> {
+            type Inner = Secret<($usize, $usize), S>;
+            fn start() -> Self {Min(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<$usize, S>) -> bool {
+                if self.0.value.is_invalid() || val.value < self.0.value {
+                    self.0 = Secret {evidence: val.evidence.map(|a| (ind, a)), value: val.value};
+                }
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+
+        impl<K: Key, S: Scalar> Lup<$usize, Secret<K, S>> for Min<($usize, K), S> {
+            type Inner = Secret<($usize, K), S>;
+            fn start() -> Self {Min(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<K, S>) -> bool {
+                if self.0.value.is_invalid() || val.value < self.0.value {
+                    self.0 = Secret {evidence: val.evidence.map(|key| (ind, key)), value: val.value};
                 }
                 true
             }
             fn unwrap(self) -> Self::Inner {self.0}
         }
 
-        impl Lup<$usize, Secret<$usize, $f32>> for Min<($usize, $usize), $f32> {
-            type Inner = Secret<($usize, $usize), $f32>;
-            fn start() -> Self {Min(Secret {evidence: None, value: $nan})}
-            fn it(&mut self, ind: $usize, val: Secret<$usize, $f32>) -> bool {
-                if self.0.value.is_nan() || val.value < self.0.value {
-                    if let Some(ind2) = val.evidence {
-                        self.0 = Secret {evidence: Some((ind, ind2)), value: val.value}
-                    }
+        impl<S: Scalar> Lup<$usize, Secret<($usize, $usize), S>> for Min<($usize, $usize, $usize), S> {
+            type Inner = Secret<($usize, $usize, $usize), S>;
+            fn start() -> Self {Min(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize), S>) -> bool {
+                if self.0.value.is_invalid() || val.value < self.0.value {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b)| (ind, a, b)),
+                        value: val.value
+                    };
                 }
                 true
             }
             fn unwrap(self) -> Self::Inner {self.0}
         }
 
-        impl Lup<$usize, Secret<($usize, $usize), $f32>> for Min<($usize, $usize, $usize), $f32> {
-            type Inner = Secret<($usize, $usize, $usize), $f32>;
-            fn start() -> Self {Min(Secret {evidence: None, value: $nan})}
-            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize), $f32>) -> bool {
-                if self.0.value.is_nan() || val.value < self.0.value {
-                    if let Some((a, b)) = val.evidence {
-                        self.0 = Secret {evidence: Some((ind, a, b)), value: val.value}
-                    }
+        impl<S: Scalar> Lup<$usize, Secret<($usize, $usize, $usize), S>>
+        for Min<($usize, $usize, $usize, $usize), S> {
+            type Inner = Secret<($usize, $usize, $usize, $usize), S>;
+            fn start() -> Self {Min(Secret {evidence: None, value: S::invalid()})}
+            fn it(&mut self, ind: $usize, val: Secret<($usize, $usize, $usize), S>) -> bool {
+                if self.0.value.is_invalid() || val.value < self.0.value {
+                    self.0 = Secret {
+                        evidence: val.evidence.map(|(a, b, c)| (ind, a, b, c)),
+                        value: val.value
+                    };
                 }
                 true
             }
@@ -47,6 +79,85 @@ macro_rules! min_impl {
     }
 }
 
-min_impl!{usize, f32, ::std::f32::NAN}
+min_impl!{usize}
+
+min_impl!{u32}
+
+min_impl!{u16}
+
+/// Minimum loop over optional values.
+///
+/// Skips `None` values, which do not count as evidence.
+/// When all values are `None`, there is no evidence.
+#[derive(Debug, Clone)]
+pub struct MinOpt<I, T>(pub Min<I, T>);
+
+impl<K, S: Scalar> Lup<K, Option<S>> for MinOpt<K, S> {
+    type Inner = Secret<K, S>;
+    fn start() -> Self {MinOpt(Min::start())}
+    fn it(&mut self, ind: K, val: Option<S>) -> bool {
+        match val {
+            Some(val) => self.0.it(ind, val),
+            None => true,
+        }
+    }
+    fn unwrap(self) -> Self::Inner {self.0.unwrap()}
+}
+
+/// Skips inner loops with `None` value, when nesting loops over `Option` values.
+impl<I, E, K, S: Scalar> Lup<I, Secret<E, Option<S>>> for MinOpt<K, S>
+    where Min<K, S>: Lup<I, Secret<E, S>>
+{
+    type Inner = <Min<K, S> as Lup<I, Secret<E, S>>>::Inner;
+    fn start() -> Self {MinOpt(<Min<K, S> as Lup<I, Secret<E, S>>>::start())}
+    fn it(&mut self, ind: I, val: Secret<E, Option<S>>) -> bool {
+        match val.value {
+            Some(value) => self.0.it(ind, Secret {evidence: val.evidence, value}),
+            None => true,
+        }
+    }
+    fn unwrap(self) -> Self::Inner {<Min<K, S> as Lup<I, Secret<E, S>>>::unwrap(self.0)}
+}
+
+/// Uses the evidence instead of a NaN sentinel to detect the first item.
+#[cfg(feature = "half")]
+impl<K> Lup<K, f16> for Min<K, f16> {
+    type Inner = Secret<K, f16>;
+    fn start() -> Self {Min(Secret {evidence: None, value: f16::NAN})}
+    fn it(&mut self, ind: K, val: f16) -> bool {
+        if self.0.evidence.is_none() || val < self.0.value {
+            self.0 = Secret {evidence: Some(ind), value: val};
+        }
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+#[cfg(feature = "glam")]
+macro_rules! min_glam_impl {
+    ($($vec:ident),*) => {$(
+        /// Componentwise minimum, without evidence.
+        ///
+        /// Returns NaN components when there are no values.
+        impl<K> Lup<K, $vec> for Min<K, $vec> {
+            type Inner = $vec;
+            fn start() -> Self {Min(Secret {evidence: None, value: $vec::NAN})}
+            fn it(&mut self, _ind: K, val: $vec) -> bool {
+                self.0.value = if self.0.value.is_nan_mask().any() {val} else {self.0.value.min(val)};
+                true
+            }
+            fn unwrap(self) -> $vec {self.0.value}
+        }
+    )*}
+}
+
+#[cfg(feature = "glam")]
+min_glam_impl!{Vec2, Vec3, Vec4, DVec2, DVec3, DVec4}
 
-min_impl!{usize, f64, ::std::f64::NAN}
+/// Minimum loop over unordered pairs of indices.
+///
+/// Used with the `(i, j) by <list>` syntax, which visits each pair once with `i < j`,
+/// e.g. to find the closest pair of points.
+/// The evidence is the pair with the smallest value.
+/// With fewer than two items, there is no evidence.
+pub type MinPair = Min<(usize, usize), f64>;
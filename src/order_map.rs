@@ -0,0 +1,89 @@
+use *;
+use std::collections::HashMap;
+use alloc::vec::Vec;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Index;
+
+/// A map that iterates in insertion order.
+///
+/// Stores the entries in a `Vec` of pairs, with an index from hashes to positions.
+/// Iteration visits the entries in the order the keys were first inserted,
+/// so the hasher never leaks into the observable order.
+/// Used as result by loops that collect keys, such as `Frequencies`.
+///
+/// Requires the `std` feature.
+#[derive(Debug, Clone)]
+pub struct OrderMap<K, V, H = RandomState> {
+    entries: Vec<(K, V)>,
+    index: HashMap<u64, Vec<usize>>,
+    hasher: H,
+}
+
+impl<K, V, H: Default> Default for OrderMap<K, V, H> {
+    fn default() -> OrderMap<K, V, H> {
+        OrderMap {entries: Vec::new(), index: HashMap::new(), hasher: H::default()}
+    }
+}
+
+impl<K, V, H> OrderMap<K, V, H> {
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {self.entries.len()}
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {self.entries.is_empty()}
+
+    /// Iterates over the entries in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Iterates over the keys in insertion order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {self.entries.iter().map(|(k, _)| k)}
+
+    /// Iterates over the values in insertion order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {self.entries.iter().map(|(_, v)| v)}
+}
+
+impl<K: Eq + Hash, V, H: BuildHasher> OrderMap<K, V, H> {
+    fn position(&self, hash: u64, key: &K) -> Option<usize> {
+        self.index.get(&hash)?.iter().cloned().find(|&i| self.entries[i].0 == *key)
+    }
+
+    /// Returns the value of a key, if any.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let hash = self.hasher.hash_one(key);
+        self.position(hash, key).map(|i| &self.entries[i].1)
+    }
+
+    /// Returns the value of a key, inserting it at the end with `f` if it is missing.
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        let hash = self.hasher.hash_one(&key);
+        let i = match self.position(hash, &key) {
+            Some(i) => i,
+            None => {
+                self.entries.push((key, f()));
+                let i = self.entries.len() - 1;
+                self.index.entry(hash).or_default().push(i);
+                i
+            }
+        };
+        &mut self.entries[i].1
+    }
+}
+
+impl<K: Eq + Hash, V, H: BuildHasher> Index<&K> for OrderMap<K, V, H> {
+    type Output = V;
+    fn index(&self, key: &K) -> &V {self.get(key).expect("OrderMap: key not found")}
+}
+
+impl<K, V, H> IntoIterator for OrderMap<K, V, H> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+    fn into_iter(self) -> Self::IntoIter {self.entries.into_iter()}
+}
+
+/// Maps are equal when they have the same entries in the same order.
+impl<K: PartialEq, V: PartialEq, H> PartialEq for OrderMap<K, V, H> {
+    fn eq(&self, other: &Self) -> bool {self.entries == other.entries}
+}
@@ -0,0 +1,37 @@
+use *;
+
+/// Maximum loop per component of array-valued bodies.
+///
+/// The result has the maximum value and its index for each component.
+#[derive(Debug, Clone)]
+pub struct MaxPerAxis<I, T, const N: usize>(pub [Secret<I, T>; N]);
+
+/// Minimum loop per component of array-valued bodies.
+///
+/// The result has the minimum value and its index for each component.
+#[derive(Debug, Clone)]
+pub struct MinPerAxis<I, T, const N: usize>(pub [Secret<I, T>; N]);
+
+macro_rules! per_axis_impl {
+    ($per_axis:ident, $cmp:ident) => {
+        impl<K: Clone, S: Scalar, const N: usize> Lup<K, [S; N]> for $per_axis<K, S, N> {
+            type Inner = [Secret<K, S>; N];
+            fn start() -> Self {
+                $per_axis(core::array::from_fn(|_| Secret {evidence: None, value: S::invalid()}))
+            }
+            fn it(&mut self, ind: K, val: [S; N]) -> bool {
+                for (acc, &v) in self.0.iter_mut().zip(val.iter()) {
+                    if acc.value.is_invalid() || v.$cmp(&acc.value) {
+                        *acc = Secret {evidence: Some(ind.clone()), value: v};
+                    }
+                }
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    }
+}
+
+per_axis_impl!{MaxPerAxis, gt}
+
+per_axis_impl!{MinPerAxis, lt}
@@ -1,54 +1,150 @@
 use *;
+#[cfg(feature = "bigint")]
+use num_bigint::{BigInt, BigUint};
 
 /// Product loop.
+#[derive(Debug, Clone)]
 pub struct Prod<T>(pub T);
 
-macro_rules! prod_impl {
-    ($usize:ty , $f32:ty) => {
-        impl Lup<$usize, $f32> for Prod<$f32> {
-            type Inner = $f32;
-            fn start() -> Prod<$f32> {Prod(1.0)}
-            fn it(&mut self, _ind: $usize, val: $f32) -> bool {self.0 *= val; true}
-            fn unwrap(self) -> $f32 {self.0}
+impl<I, S: Scalar> Lup<I, S> for Prod<S> {
+    type Inner = S;
+    fn start() -> Prod<S> {Prod(S::one())}
+    fn it(&mut self, _ind: I, val: S) -> bool {self.0 = S::mul(self.0, val); true}
+    fn unwrap(self) -> S {self.0}
+}
+
+/// Starts the product at a seed value, e.g. `lup!(Prod<f64> with 10.0: ...)`.
+impl<I, T> LupWith<T, I, T> for Prod<T> where Prod<T>: Lup<I, T> {
+    fn start_with(seed: T) -> Prod<T> {Prod(seed)}
+}
+
+impl<I, S: Scalar> Lup<I, [S; 2]> for Prod<[S; 2]> {
+    type Inner = [S; 2];
+    fn start() -> Prod<[S; 2]> {Prod([S::one(); 2])}
+    fn it(&mut self, _ind: I, val: [S; 2]) -> bool {
+        self.0 = [S::mul(self.0[0], val[0]), S::mul(self.0[1], val[1])];
+        true
+    }
+    fn unwrap(self) -> [S; 2] {self.0}
+}
+
+impl<I, S: Scalar> Lup<I, [S; 3]> for Prod<[S; 3]> {
+    type Inner = [S; 3];
+    fn start() -> Prod<[S; 3]> {Prod([S::one(); 3])}
+    fn it(&mut self, _ind: I, val: [S; 3]) -> bool {
+        self.0 = [
+            S::mul(self.0[0], val[0]),
+            S::mul(self.0[1], val[1]),
+            S::mul(self.0[2], val[2]),
+        ];
+        true
+    }
+    fn unwrap(self) -> [S; 3] {self.0}
+}
+
+impl<I, S: Scalar> Lup<I, [S; 4]> for Prod<[S; 4]> {
+    type Inner = [S; 4];
+    fn start() -> Prod<[S; 4]> {Prod([S::one(); 4])}
+    fn it(&mut self, _ind: I, val: [S; 4]) -> bool {
+        self.0 = [
+            S::mul(self.0[0], val[0]),
+            S::mul(self.0[1], val[1]),
+            S::mul(self.0[2], val[2]),
+            S::mul(self.0[3], val[3]),
+        ];
+        true
+    }
+    fn unwrap(self) -> [S; 4] {self.0}
+}
+
+macro_rules! prod_int_impl {
+    ($($int:ty),*) => {$(
+        impl<I> Lup<I, $int> for Prod<$int> {
+            type Inner = $int;
+            fn start() -> Prod<$int> {Prod(1)}
+            fn it(&mut self, _ind: I, val: $int) -> bool {self.0 *= val; true}
+            fn unwrap(self) -> $int {self.0}
         }
+    )*}
+}
 
-        impl Lup<$usize, [$f32; 2]> for Prod<[$f32; 2]> {
-            type Inner = [$f32; 2];
-            fn start() -> Prod<[$f32; 2]> {Prod([1.0; 2])}
-            fn it(&mut self, _ind: $usize, val: [$f32; 2]) -> bool {
-                self.0 = [self.0[0] * val[0], self.0[1] * val[1]];
-                true
+prod_int_impl!{u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize}
+
+/// Product loop that detects when the product stops being finite.
+///
+/// The evidence is the index of the factor that made the product infinite or NaN,
+/// and the value is the product at that point.
+/// The loop stops there, since a product that is infinite or NaN
+/// can never become finite again.
+#[derive(Debug, Clone)]
+pub struct ProdChecked<T>(pub Secret<usize, T>);
+
+macro_rules! prod_checked_impl {
+    ($f32:ty) => {
+        impl Lup<usize, $f32> for ProdChecked<$f32> {
+            type Inner = Secret<usize, $f32>;
+            fn start() -> Self {ProdChecked(Secret {evidence: None, value: 1.0})}
+            fn it(&mut self, ind: usize, val: $f32) -> bool {
+                self.0.value *= val;
+                if self.0.value.is_finite() {
+                    true
+                } else {
+                    self.0.evidence = Some(ind);
+                    false
+                }
             }
-            fn unwrap(self) -> [$f32; 2] {self.0}
+            fn unwrap(self) -> Self::Inner {self.0}
         }
+    }
+}
+
+prod_checked_impl!{f32}
+
+prod_checked_impl!{f64}
 
-        impl Lup<$usize, [$f32; 3]> for Prod<[$f32; 3]> {
-            type Inner = [$f32; 3];
-            fn start() -> Prod<[$f32; 3]> {Prod([1.0; 3])}
-            fn it(&mut self, _ind: $usize, val: [$f32; 3]) -> bool {
-                self.0 = [self.0[0] * val[0], self.0[1] * val[1], self.0[2] * val[2]];
+#[cfg(feature = "mint")]
+macro_rules! prod_mint_impl {
+    ($($vec:ident : $n:tt),*) => {$(
+        impl<I, S: Scalar> Lup<I, mint::$vec<S>> for Prod<mint::$vec<S>> {
+            type Inner = mint::$vec<S>;
+            fn start() -> Self {Prod([S::one(); $n].into())}
+            fn it(&mut self, ind: I, val: mint::$vec<S>) -> bool {
+                let mut acc = Prod::<[S; $n]>(self.0.into());
+                acc.it(ind, val.into());
+                self.0 = acc.0.into();
                 true
             }
-            fn unwrap(self) -> [$f32; 3] {self.0}
+            fn unwrap(self) -> mint::$vec<S> {self.0}
         }
+    )*}
+}
 
-        impl Lup<$usize, [$f32; 4]> for Prod<[$f32; 4]> {
-            type Inner = [$f32; 4];
-            fn start() -> Prod<[$f32; 4]> {Prod([1.0; 4])}
-            fn it(&mut self, _ind: $usize, val: [$f32; 4]) -> bool {
-                self.0 = [
-                    self.0[0] * val[0],
-                    self.0[1] * val[1],
-                    self.0[2] * val[2],
-                    self.0[3] * val[3],
-                ];
-                true
-            }
-            fn unwrap(self) -> [$f32; 4] {self.0}
+#[cfg(feature = "mint")]
+prod_mint_impl!{Vector2: 2, Vector3: 3, Vector4: 4}
+
+#[cfg(feature = "bigint")]
+macro_rules! prod_bigint_impl {
+    ($big:ident : $($t:ty),*) => {
+        impl<I> Lup<I, $big> for Prod<$big> {
+            type Inner = $big;
+            fn start() -> Self {Prod($big::from(1u8))}
+            fn it(&mut self, _ind: I, val: $big) -> bool {self.0 *= val; true}
+            fn unwrap(self) -> $big {self.0}
         }
+
+        $(
+            impl<I> Lup<I, $t> for Prod<$big> {
+                type Inner = $big;
+                fn start() -> Self {Prod($big::from(1u8))}
+                fn it(&mut self, _ind: I, val: $t) -> bool {self.0 *= val; true}
+                fn unwrap(self) -> $big {self.0}
+            }
+        )*
     }
 }
 
-prod_impl!{usize, f32}
+#[cfg(feature = "bigint")]
+prod_bigint_impl!{BigUint: u8, u16, u32, u64, u128, usize}
 
-prod_impl!{usize, f64}
+#[cfg(feature = "bigint")]
+prod_bigint_impl!{BigInt: i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize}
@@ -0,0 +1,20 @@
+use *;
+
+/// There exists loop, an alias for `Any` when proving statements.
+pub type Exists<I> = Any<I>;
+
+/// For all loop, an alias for `All` when proving statements.
+pub type Forall<I> = All<I>;
+
+/// A counterexample returned by `prove!` when a statement does not hold.
+///
+/// For statements starting with `forall`, the evidence is the path of indices
+/// where the statement fails.
+/// For ranges, the indices are the values of the quantified variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counterexample<E, V> {
+    /// The indices where the statement fails, if known.
+    pub evidence: Option<E>,
+    /// The values of the leading `forall` variables where the statement fails, if known.
+    pub values: Option<V>,
+}
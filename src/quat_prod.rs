@@ -0,0 +1,61 @@
+use *;
+
+/// Quaternion product loop.
+///
+/// Composes quaternions `[x, y, z, w]` with the Hamilton product,
+/// starting with the identity `[0, 0, 0, 1]`.
+/// The product is taken in loop order, `q0 * q1 * ... * qn`,
+/// so a vector is rotated by the last quaternion first, like for `MatMul`.
+///
+/// Rounding errors make long products drift away from unit length.
+/// The loop can be constructed with a number of steps `k`
+/// using the constructor syntax of `lup!`, e.g. `lup!(QuatProd(16): ...)`,
+/// to renormalize the product every `k` steps.
+/// The renormalization uses one Newton step instead of a square root,
+/// which is accurate when the product is close to unit length.
+/// When started without a constructor, the product is never renormalized.
+#[derive(Debug, Clone)]
+pub struct QuatProd<T = f32>(pub [T; 4], pub usize, pub usize);
+
+impl<T> QuatProd<T> where QuatProd<T>: Lup<usize, [T; 4]> {
+    /// Creates a new loop renormalizing every `k` steps.
+    ///
+    /// With `k = 0`, the product is never renormalized.
+    pub fn new(k: usize) -> QuatProd<T> {
+        let mut q = <QuatProd<T> as Lup<usize, [T; 4]>>::start();
+        q.1 = k;
+        q
+    }
+}
+
+macro_rules! quat_prod_impl {
+    ($($f32:ty),*) => {$(
+        impl<I> Lup<I, [$f32; 4]> for QuatProd<$f32> {
+            type Inner = [$f32; 4];
+            fn start() -> Self {QuatProd([0.0, 0.0, 0.0, 1.0], 0, 0)}
+            fn it(&mut self, _ind: I, [bx, by, bz, bw]: [$f32; 4]) -> bool {
+                let [ax, ay, az, aw] = self.0;
+                self.0 = [
+                    aw * bx + ax * bw + ay * bz - az * by,
+                    aw * by - ax * bz + ay * bw + az * bx,
+                    aw * bz + ax * by - ay * bx + az * bw,
+                    aw * bw - ax * bx - ay * by - az * bz,
+                ];
+                self.2 += 1;
+                if self.1 > 0 && self.2 % self.1 == 0 {
+                    let n2: $f32 = self.0.iter().map(|x| x * x).sum();
+                    let s = (3.0 - n2) / 2.0;
+                    for x in &mut self.0 {*x *= s}
+                }
+                true
+            }
+            fn unwrap(self) -> [$f32; 4] {self.0}
+        }
+
+        impl<I> LupWith<usize, I, [$f32; 4]> for QuatProd<$f32> {
+            fn start_with(k: usize) -> Self {QuatProd([0.0, 0.0, 0.0, 1.0], k, 0)}
+        }
+    )*}
+}
+
+quat_prod_impl!{f32, f64}
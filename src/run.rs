@@ -0,0 +1,58 @@
+use *;
+
+/// Runs a custom loop without the `lup!` macro.
+///
+/// The body is called with each index from the iterator,
+/// and the result is passed to the loop together with the index.
+///
+/// Unlike the macro, the body is a closure,
+/// so it can not use `continue`, `break` or `return` on the surrounding code.
+///
+/// ```
+/// use lup::{run, Sum};
+///
+/// let list = [1.0, 2.0, 3.0];
+/// let sum = run::<_, _, Sum<f64>>(0..list.len(), |i| list[i]);
+/// assert_eq!(sum, 6.0);
+/// ```
+pub fn run<I, T, L>(iter: impl Iterator<Item = I>, mut body: impl FnMut(I) -> T) -> L::Inner
+    where I: Clone, L: Lup<I, T>
+{
+    let mut lup: Driver<L, I, T> = Driver::start_for(&iter);
+    for ind in iter {
+        let val = body(ind.clone());
+        if !lup.it(ind, val) {break};
+    }
+    lup.unwrap()
+}
+
+/// Runs an `Any` loop without the `lup!` macro.
+pub fn any<I: Clone>(iter: impl Iterator<Item = I>, body: impl FnMut(I) -> bool) -> Secret<I, bool> {
+    run::<_, _, Any<I>>(iter, body)
+}
+
+/// Runs an `All` loop without the `lup!` macro.
+pub fn all<I: Clone>(iter: impl Iterator<Item = I>, body: impl FnMut(I) -> bool) -> Secret<I, bool> {
+    run::<_, _, All<I>>(iter, body)
+}
+
+/// Runs a `Max` loop without the `lup!` macro.
+pub fn max<I: Clone, T>(iter: impl Iterator<Item = I>, body: impl FnMut(I) -> T) -> Secret<I, T>
+    where Max<I, T>: Lup<I, T, Inner = Secret<I, T>>
+{
+    run::<_, _, Max<I, T>>(iter, body)
+}
+
+/// Runs a `Min` loop without the `lup!` macro.
+pub fn min<I: Clone, T>(iter: impl Iterator<Item = I>, body: impl FnMut(I) -> T) -> Secret<I, T>
+    where Min<I, T>: Lup<I, T, Inner = Secret<I, T>>
+{
+    run::<_, _, Min<I, T>>(iter, body)
+}
+
+/// Runs a `Sum` loop without the `lup!` macro.
+pub fn sum<I: Clone, T>(iter: impl Iterator<Item = I>, body: impl FnMut(I) -> T) -> T
+    where Sum<T>: Lup<I, T, Inner = T>
+{
+    run::<_, _, Sum<T>>(iter, body)
+}
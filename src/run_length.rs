@@ -0,0 +1,44 @@
+use *;
+
+/// Longest run loop.
+///
+/// Finds the longest run of consecutive items where the body is `true`.
+/// The value is the length of the run,
+/// and the evidence is the range `(start, end)` of the run, with `end` exclusive.
+/// Ties keep the first run.
+///
+/// When the body is never `true`, the length is zero and there is no evidence.
+#[derive(Debug, Clone)]
+pub struct LongestRun {
+    /// The longest run so far.
+    pub best: Secret<(usize, usize), usize>,
+    /// The start and length of the current run.
+    pub current: Option<(usize, usize)>,
+}
+
+impl Default for LongestRun {
+    fn default() -> LongestRun {
+        LongestRun {best: Secret {evidence: None, value: 0}, current: None}
+    }
+}
+
+impl Lup<usize, bool> for LongestRun {
+    type Inner = Secret<(usize, usize), usize>;
+    fn start() -> LongestRun {LongestRun::default()}
+    fn it(&mut self, ind: usize, val: bool) -> bool {
+        if val {
+            let (start, len) = match self.current {
+                Some((start, len)) => (start, len + 1),
+                None => (ind, 1),
+            };
+            self.current = Some((start, len));
+            if len > self.best.value {
+                self.best = Secret {evidence: Some((start, ind + 1)), value: len};
+            }
+        } else {
+            self.current = None;
+        }
+        true
+    }
+    fn unwrap(self) -> Secret<(usize, usize), usize> {self.best}
+}
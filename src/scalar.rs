@@ -0,0 +1,40 @@
+/// Implemented by scalar numbers used by `Sum`, `Prod`, `Max` and `Min`.
+///
+/// This is implemented for `f32` and `f64`.
+/// Implement it for your own number types, e.g. fixed point numbers,
+/// to use them with the loops.
+///
+/// `Max` and `Min` start with an invalid value,
+/// which is replaced by the first item in the loop.
+/// For floats, the invalid value is NaN.
+pub trait Scalar: Copy + PartialOrd {
+    /// Returns zero, the start value of `Sum`.
+    fn zero() -> Self;
+    /// Returns one, the start value of `Prod`.
+    fn one() -> Self;
+    /// Adds two scalars.
+    fn add(self, other: Self) -> Self;
+    /// Multiplies two scalars.
+    fn mul(self, other: Self) -> Self;
+    /// Returns the invalid value.
+    fn invalid() -> Self;
+    /// Returns `true` if the value is invalid.
+    fn is_invalid(self) -> bool;
+}
+
+macro_rules! scalar_impl {
+    ($f32:ident) => {
+        impl Scalar for $f32 {
+            fn zero() -> $f32 {0.0}
+            fn one() -> $f32 {1.0}
+            fn add(self, other: $f32) -> $f32 {self + other}
+            fn mul(self, other: $f32) -> $f32 {self * other}
+            fn invalid() -> $f32 {$f32::NAN}
+            fn is_invalid(self) -> bool {self.is_nan()}
+        }
+    }
+}
+
+scalar_impl!{f32}
+
+scalar_impl!{f64}
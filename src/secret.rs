@@ -1,5 +1,9 @@
-use std::ops::{Neg, Not};
-use std::cmp::{PartialOrd, PartialEq};
+use core::ops::{Neg, Not};
+use core::cmp::{PartialOrd, PartialEq};
+#[cfg(feature = "proptest")]
+use proptest::arbitrary::{any, Arbitrary};
+#[cfg(feature = "proptest")]
+use proptest::strategy::{BoxedStrategy, Strategy};
 
 /// Stores a secret.
 ///
@@ -35,6 +39,12 @@ impl<E> Not for Secret<E, bool> {
 }
 
 impl<E, T> Secret<E, T> {
+    /// Takes the evidence out of the secret, leaving `None` in its place.
+    pub fn take_evidence(&mut self) -> Option<E> {self.evidence.take()}
+
+    /// Returns a mutable reference to the evidence, if any.
+    pub fn evidence_mut(&mut self) -> Option<&mut E> {self.evidence.as_mut()}
+
     /// Checks if secret value is less than value.
     ///
     /// This is a method because Rust does not allow overriding
@@ -113,3 +123,80 @@ impl<E, T> Secret<E, T> {
         }
     }
 }
+
+/// Merging results of loops over chunks of a list.
+///
+/// The second result comes from a chunk starting at `offset`,
+/// so its evidence is shifted by `offset` before merging.
+/// The first result is assumed to have evidence in the whole list already,
+/// e.g. from an earlier merge or from the first chunk with offset 0.
+///
+/// Ties are resolved to the lowest index,
+/// which gives the same result as a single loop over the whole list,
+/// no matter in which order the chunks are merged.
+/// A result without evidence, e.g. from an empty chunk, loses to one with evidence.
+impl<T: PartialOrd> Secret<usize, T> {
+    /// Merges two results of `Max` loops.
+    pub fn merge_max(a: Self, b: Self, offset: usize) -> Self {
+        Secret::merge_by(a, b, offset, |a, b| a > b)
+    }
+
+    /// Merges two results of `Min` loops.
+    pub fn merge_min(a: Self, b: Self, offset: usize) -> Self {
+        Secret::merge_by(a, b, offset, |a, b| a < b)
+    }
+
+    fn merge_by(a: Self, b: Self, offset: usize, better: fn(&T, &T) -> bool) -> Self {
+        let b = Secret {evidence: b.evidence.map(|i| i + offset), value: b.value};
+        match (a.evidence, b.evidence) {
+            (None, _) => b,
+            (_, None) => a,
+            (Some(i), Some(j)) => {
+                if better(&b.value, &a.value) || (!better(&a.value, &b.value) && j < i) {b} else {a}
+            }
+        }
+    }
+}
+
+impl Secret<usize, bool> {
+    /// Merges two results of `Any` loops.
+    ///
+    /// The evidence is the lowest index that satisfies the condition.
+    pub fn merge_any(a: Self, b: Self, offset: usize) -> Self {
+        Secret::merge_found(a, b, offset, true)
+    }
+
+    /// Merges two results of `All` loops.
+    ///
+    /// The evidence is the lowest index of a counterexample.
+    pub fn merge_all(a: Self, b: Self, offset: usize) -> Self {
+        Secret::merge_found(a, b, offset, false)
+    }
+
+    fn merge_found(a: Self, b: Self, offset: usize, found: bool) -> Self {
+        let b = Secret {evidence: b.evidence.map(|i| i + offset), value: b.value};
+        match (a.value == found, b.value == found) {
+            (true, true) => match (a.evidence, b.evidence) {
+                (Some(i), Some(j)) if j < i => b,
+                (None, Some(_)) => b,
+                _ => a,
+            },
+            (true, false) => a,
+            (false, true) => b,
+            (false, false) => Secret {evidence: None, value: !found},
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl<E, T> Arbitrary for Secret<E, T>
+    where E: Arbitrary + 'static, T: Arbitrary + 'static
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Secret<E, T>>;
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<Option<E>>(), any::<T>())
+            .prop_map(|(evidence, value)| Secret {evidence, value})
+            .boxed()
+    }
+}
@@ -0,0 +1,21 @@
+/// Implemented by 2D containers that can be looped over with the `by2` syntax.
+///
+/// This is used by `lup!` for containers that are indexed with a pair of indices,
+/// such as `a[[i, j]]`, instead of nested indexing.
+pub trait Shape2 {
+    /// Returns the number of rows.
+    fn rows(&self) -> usize;
+    /// Returns the number of columns.
+    fn cols(&self) -> usize;
+}
+
+impl<T: Shape2> Shape2 for &T {
+    fn rows(&self) -> usize {T::rows(self)}
+    fn cols(&self) -> usize {T::cols(self)}
+}
+
+#[cfg(feature = "ndarray")]
+impl<S: ::ndarray::RawData> Shape2 for ::ndarray::ArrayBase<S, ::ndarray::Ix2> {
+    fn rows(&self) -> usize {self.nrows()}
+    fn cols(&self) -> usize {self.ncols()}
+}
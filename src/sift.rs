@@ -1,15 +1,281 @@
 use *;
+use alloc::vec::Vec;
 
 /// A loop that creates a list.
+#[derive(Debug, Clone, Default)]
 pub struct Sift<T>(pub T);
 
 impl<T> Lup<usize, T> for Sift<Vec<T>> {
     type Inner = Vec<T>;
 
-    fn start() -> Self {Sift(vec![])}
+    fn start() -> Self {Sift(Vec::new())}
+    fn start_sized(hint: Option<usize>) -> Self {
+        Sift(Vec::with_capacity(hint.unwrap_or(0)))
+    }
     fn it(&mut self, _ind: usize, val: T) -> bool {
         self.0.push(val);
         true
     }
     fn unwrap(self) -> Self::Inner {self.0}
 }
+
+/// A loop that creates a list, placing each item at its index.
+///
+/// The list grows as needed, filling gaps with default values,
+/// so stepped, reversed or out of order loops build a table indexed by the loop index.
+#[derive(Debug, Clone, Default)]
+pub struct SiftIndexed<T>(pub T);
+
+impl<T: Default> Lup<usize, T> for SiftIndexed<Vec<T>> {
+    type Inner = Vec<T>;
+
+    fn start() -> Self {SiftIndexed(Vec::new())}
+    fn it(&mut self, ind: usize, val: T) -> bool {
+        if ind >= self.0.len() {
+            self.0.resize_with(ind + 1, T::default);
+        }
+        self.0[ind] = val;
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+/// A loop that creates a list of the running product.
+///
+/// The item at each position is the product of all values up to and including it.
+#[derive(Debug, Clone, Default)]
+pub struct CumProd<T>(pub Vec<T>);
+
+macro_rules! cum_prod_impl {
+    ($f32:ty) => {
+        impl Lup<usize, $f32> for CumProd<$f32> {
+            type Inner = Vec<$f32>;
+
+            fn start() -> Self {CumProd(Vec::new())}
+            fn start_sized(hint: Option<usize>) -> Self {
+                CumProd(Vec::with_capacity(hint.unwrap_or(0)))
+            }
+            fn it(&mut self, _ind: usize, val: $f32) -> bool {
+                let prod = self.0.last().map_or(val, |&last| last * val);
+                self.0.push(prod);
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    }
+}
+
+cum_prod_impl!{f32}
+
+cum_prod_impl!{f64}
+
+/// A loop that creates a list of softmax probabilities.
+///
+/// Collects the values, then when the loop ends,
+/// subtracts the maximum for numerical stability,
+/// exponentiates and normalizes them so they sum to 1.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct Softmax(pub Vec<f64>);
+
+#[cfg(feature = "std")]
+impl Lup<usize, f64> for Softmax {
+    type Inner = Vec<f64>;
+
+    fn start() -> Self {Softmax(Vec::new())}
+    fn start_sized(hint: Option<usize>) -> Self {
+        Softmax(Vec::with_capacity(hint.unwrap_or(0)))
+    }
+    fn it(&mut self, _ind: usize, val: f64) -> bool {
+        self.0.push(val);
+        true
+    }
+    fn unwrap(mut self) -> Self::Inner {
+        let max = self.0.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let mut sum = 0.0;
+        for x in self.0.iter_mut() {
+            *x = (*x - max).exp();
+            sum += *x;
+        }
+        for x in self.0.iter_mut() {*x /= sum}
+        self.0
+    }
+}
+
+/// A loop that creates a flat row-major buffer of a given size.
+///
+/// The loop is constructed with `(height, width)` using the constructor syntax of `lup!`,
+/// and is driven by two indices, writing each value at `i * width + j`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::FlatVector;
+///
+/// fn main() {
+///     let (h, w) = (2, 3);
+///     let buf: Vec<f32> = lup!(FlatVector(h, w): i in 0..h, j in 0..w => {(i + j) as f32});
+///     println!("{:?}", buf); // Prints `[0.0, 1.0, 2.0, 1.0, 2.0, 3.0]`.
+/// }
+/// ```
+///
+/// Items that are not visited keep their default value.
+/// Panics if an index is out of range.
+#[derive(Debug, Clone, Default)]
+pub struct FlatVector<T>(pub Vec<T>, pub [usize; 2]);
+
+impl<T: Default> FlatVector<T> {
+    /// Creates a new loop with a buffer of `height * width` default values.
+    pub fn new(height: usize, width: usize) -> FlatVector<T> {
+        let mut buf = Vec::new();
+        buf.resize_with(height * width, T::default);
+        FlatVector(buf, [height, width])
+    }
+}
+
+impl<T: Default> Lup<(usize, usize), T> for FlatVector<T> {
+    type Inner = Vec<T>;
+
+    fn start() -> Self {FlatVector::new(0, 0)}
+    fn it(&mut self, (i, j): (usize, usize), val: T) -> bool {
+        let [h, w] = self.1;
+        assert!(i < h && j < w, "FlatVector: index ({}, {}) out of range for ({}, {})", i, j, h, w);
+        self.0[i * w + j] = val;
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+impl<T: Default> LupWith<(usize, usize), (usize, usize), T> for FlatVector<T> {
+    fn start_with((height, width): (usize, usize)) -> Self {FlatVector::new(height, width)}
+}
+
+/// A loop that creates a sparse list of `(index, value)` pairs.
+///
+/// The body returns `Option<T>`, and only the `Some` values are collected,
+/// together with the index where they came from.
+/// With two packed indices, the index is `(usize, usize)`.
+#[derive(Debug, Clone, Default)]
+pub struct SparseSift<T>(pub T);
+
+impl<T> Lup<usize, Option<T>> for SparseSift<Vec<(usize, T)>> {
+    type Inner = Vec<(usize, T)>;
+
+    fn start() -> Self {SparseSift(Vec::new())}
+    fn it(&mut self, ind: usize, val: Option<T>) -> bool {
+        if let Some(val) = val {self.0.push((ind, val))}
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+impl<T> Lup<usize, Vec<(usize, T)>> for SparseSift<Vec<((usize, usize), T)>> {
+    type Inner = Vec<((usize, usize), T)>;
+
+    fn start() -> Self {SparseSift(Vec::new())}
+    fn it(&mut self, ind: usize, val: Vec<(usize, T)>) -> bool {
+        self.0.extend(val.into_iter().map(|(j, val)| ((ind, j), val)));
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+/// A loop that creates a list of values rescaled to `[0, 1]`.
+///
+/// Collects the values, then when the loop ends,
+/// maps the minimum to 0 and the maximum to 1.
+/// When all values are equal, the result is all zeros.
+///
+/// NaN values are ignored when finding the minimum and maximum,
+/// and stay NaN in the result.
+#[derive(Debug, Clone, Default)]
+pub struct Normalize(pub Vec<f64>);
+
+impl Lup<usize, f64> for Normalize {
+    type Inner = Vec<f64>;
+
+    fn start() -> Self {Normalize(Vec::new())}
+    fn start_sized(hint: Option<usize>) -> Self {
+        Normalize(Vec::with_capacity(hint.unwrap_or(0)))
+    }
+    fn it(&mut self, _ind: usize, val: f64) -> bool {
+        self.0.push(val);
+        true
+    }
+    fn unwrap(mut self) -> Self::Inner {
+        let min = self.0.iter().cloned().fold(f64::NAN, f64::min);
+        let max = self.0.iter().cloned().fold(f64::NAN, f64::max);
+        let range = max - min;
+        for x in self.0.iter_mut() {
+            if x.is_nan() {continue}
+            *x = if range > 0.0 {(*x - min) / range} else {0.0};
+        }
+        self.0
+    }
+}
+
+/// A loop that creates a list of standardized values.
+///
+/// Collects the values, then when the loop ends,
+/// maps each value `x` to `(x - mean) / stddev`,
+/// using the population standard deviation.
+/// The result has mean 0 and standard deviation 1.
+/// When all values are equal, the result is all zeros.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct ZScore(pub Vec<f64>);
+
+#[cfg(feature = "std")]
+impl Lup<usize, f64> for ZScore {
+    type Inner = Vec<f64>;
+
+    fn start() -> Self {ZScore(Vec::new())}
+    fn start_sized(hint: Option<usize>) -> Self {
+        ZScore(Vec::with_capacity(hint.unwrap_or(0)))
+    }
+    fn it(&mut self, _ind: usize, val: f64) -> bool {
+        self.0.push(val);
+        true
+    }
+    fn unwrap(mut self) -> Self::Inner {
+        let n = self.0.len() as f64;
+        let mean = self.0.iter().sum::<f64>() / n;
+        let var = self.0.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+        let stddev = var.sqrt();
+        for x in self.0.iter_mut() {
+            *x = if stddev > 0.0 {(*x - mean) / stddev} else {0.0};
+        }
+        self.0
+    }
+}
+
+/// A loop that creates a sorted list of distinct values.
+///
+/// Collects the values, then when the loop ends,
+/// sorts them in ascending order and removes duplicates,
+/// e.g. to build an index of distinct keys.
+#[derive(Debug, Clone, Default)]
+pub struct SortedUniqueSift<T>(pub Vec<T>);
+
+impl<T: Ord> Lup<usize, T> for SortedUniqueSift<T> {
+    type Inner = Vec<T>;
+
+    fn start() -> Self {SortedUniqueSift(Vec::new())}
+    fn start_sized(hint: Option<usize>) -> Self {
+        SortedUniqueSift(Vec::with_capacity(hint.unwrap_or(0)))
+    }
+    fn it(&mut self, _ind: usize, val: T) -> bool {
+        self.0.push(val);
+        true
+    }
+    fn unwrap(mut self) -> Self::Inner {
+        self.0.sort_unstable();
+        self.0.dedup();
+        self.0
+    }
+}
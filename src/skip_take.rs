@@ -0,0 +1,102 @@
+use *;
+
+/// Loop that drops the first `N` values before passing the rest to an inner loop.
+///
+/// The indices passed to the inner loop are unchanged,
+/// so evidence refers to positions in the whole loop.
+#[derive(Debug, Clone)]
+pub struct SkipLup<L, const N: usize>(pub L, pub usize);
+
+impl<I, T, L: Lup<I, T>, const N: usize> Lup<I, T> for SkipLup<L, N> {
+    type Inner = L::Inner;
+    fn start() -> Self {SkipLup(L::start(), 0)}
+    fn start_sized(hint: Option<usize>) -> Self {SkipLup(L::start_sized(hint.map(|n| n.saturating_sub(N))), 0)}
+    fn it(&mut self, ind: I, val: T) -> bool {
+        if self.1 < N {
+            self.1 += 1;
+            true
+        } else {
+            self.0.it(ind, val)
+        }
+    }
+    fn unwrap(self) -> Self::Inner {self.0.unwrap()}
+    fn finish(self, iterations: usize) -> Self::Inner {self.0.finish(iterations.saturating_sub(self.1))}
+}
+
+/// Loop that stops after passing `N` values to an inner loop.
+///
+/// This bounds the work of searches over large ranges,
+/// e.g. `TakeLup<Any<usize>, 1000>`.
+#[derive(Debug, Clone)]
+pub struct TakeLup<L, const N: usize>(pub L, pub usize);
+
+impl<I, T, L: Lup<I, T>, const N: usize> Lup<I, T> for TakeLup<L, N> {
+    type Inner = L::Inner;
+    fn start() -> Self {TakeLup(L::start(), 0)}
+    fn start_sized(hint: Option<usize>) -> Self {TakeLup(L::start_sized(hint.map(|n| n.min(N))), 0)}
+    fn it(&mut self, ind: I, val: T) -> bool {
+        if self.1 >= N {return false}
+        self.1 += 1;
+        self.0.it(ind, val) && self.1 < N
+    }
+    fn unwrap(self) -> Self::Inner {self.0.unwrap()}
+    fn finish(self, _iterations: usize) -> Self::Inner {
+        let taken = self.1;
+        self.0.finish(taken)
+    }
+}
+
+/// Loop that stops an inner loop after at most a given number of values.
+///
+/// Unlike `TakeLup`, the cap is given at runtime with the context syntax of `lup!`,
+/// e.g. `lup!(MaxIter<Any<_>> with 1000: i in 0.. => {...})`,
+/// and the result tells whether the cap was hit.
+/// This makes it safe to loop over unbounded iterators with loops that
+/// only stop by themselves in some cases, such as `Any` without a witness.
+///
+/// The result is truncated when a value arrives after the cap is reached,
+/// so a loop with exactly as many values as the cap is completed.
+/// When started without a context, there is no cap.
+#[derive(Debug, Clone)]
+pub struct MaxIter<L>(pub L, pub usize, pub usize, pub bool);
+
+/// The result of a `MaxIter` loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bounded<T> {
+    /// The result of the inner loop.
+    pub inner: T,
+    /// The number of values passed to the inner loop.
+    pub iterations: usize,
+    /// Whether the loop was stopped by the cap.
+    pub truncated: bool,
+}
+
+impl<T> Bounded<T> {
+    /// Returns `true` if the inner loop ran to completion or stopped by itself.
+    pub fn completed(&self) -> bool {!self.truncated}
+}
+
+impl<I, T, L: Lup<I, T>> Lup<I, T> for MaxIter<L> {
+    type Inner = Bounded<L::Inner>;
+    fn start() -> Self {MaxIter(L::start(), 0, usize::MAX, false)}
+    fn it(&mut self, ind: I, val: T) -> bool {
+        if self.1 >= self.2 {
+            self.3 = true;
+            return false
+        }
+        self.1 += 1;
+        self.0.it(ind, val)
+    }
+    fn unwrap(self) -> Self::Inner {
+        let iterations = self.1;
+        self.finish(iterations)
+    }
+    fn finish(self, _iterations: usize) -> Self::Inner {
+        let iterations = self.1;
+        Bounded {inner: self.0.finish(iterations), iterations, truncated: self.3}
+    }
+}
+
+impl<I, T, L: Lup<I, T>> LupWith<usize, I, T> for MaxIter<L> {
+    fn start_with(cap: usize) -> Self {MaxIter(L::start(), 0, cap, false)}
+}
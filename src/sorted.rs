@@ -0,0 +1,47 @@
+use *;
+
+/// Sorted loop.
+///
+/// The value is `true` if each value is greater or equal than the previous one.
+/// Otherwise, the evidence is the index of the first value that is out of order.
+///
+/// Values that can not be compared with themselves, such as NaN, are out of order.
+#[derive(Debug, Clone)]
+pub struct IsSorted<T>(pub Secret<usize, bool>, pub Option<T>);
+
+/// Strictly sorted loop.
+///
+/// Same as `IsSorted`, but each value must be greater than the previous one.
+#[derive(Debug, Clone)]
+pub struct IsStrictlySorted<T>(pub Secret<usize, bool>, pub Option<T>);
+
+macro_rules! sorted_impl {
+    ($sorted:ident, $prev:ident, $val:ident, $in_order:expr) => {
+        impl<T> Default for $sorted<T> {
+            fn default() -> $sorted<T> {$sorted(Secret {evidence: None, value: true}, None)}
+        }
+
+        impl<T: PartialOrd> Lup<usize, T> for $sorted<T> {
+            type Inner = Secret<usize, bool>;
+            fn start() -> Self {$sorted::default()}
+            fn it(&mut self, ind: usize, $val: T) -> bool {
+                let in_order = $val.partial_cmp(&$val).is_some() && match self.1 {
+                    Some(ref $prev) => $in_order,
+                    None => true,
+                };
+                if in_order {
+                    self.1 = Some($val);
+                    true
+                } else {
+                    self.0 = Secret {evidence: Some(ind), value: false};
+                    false
+                }
+            }
+            fn unwrap(self) -> Secret<usize, bool> {self.0}
+        }
+    }
+}
+
+sorted_impl!{IsSorted, prev, val, val >= *prev}
+
+sorted_impl!{IsStrictlySorted, prev, val, val > *prev}
@@ -0,0 +1,472 @@
+use *;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Covariance loop over paired samples.
+///
+/// The body returns `(x, y)` pairs.
+/// Computes the population covariance in a single pass,
+/// updating the co-moment as in Welford's algorithm.
+///
+/// Returns NaN when there are no samples, and `0.0` for a single sample.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Covariance;
+///
+/// fn main() {
+///     let xs = [1.0, 2.0, 3.0];
+///     let ys = [2.0, 4.0, 6.0];
+///     let cov = lup!(Covariance: i by xs => {(xs[i], ys[i])});
+///     println!("{}", cov); // Prints `1.3333333333333333`.
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Covariance {
+    n: usize,
+    mean_x: f64,
+    mean_y: f64,
+    co_moment: f64,
+}
+
+impl<I> Lup<I, (f64, f64)> for Covariance {
+    type Inner = f64;
+    fn start() -> Covariance {Covariance::default()}
+    fn it(&mut self, _ind: I, (x, y): (f64, f64)) -> bool {
+        self.n += 1;
+        let dx = x - self.mean_x;
+        self.mean_x += dx / self.n as f64;
+        self.mean_y += (y - self.mean_y) / self.n as f64;
+        self.co_moment += dx * (y - self.mean_y);
+        true
+    }
+    fn unwrap(self) -> f64 {self.co_moment / self.n as f64}
+}
+
+/// Pearson correlation loop over paired samples.
+///
+/// The body returns `(x, y)` pairs.
+/// Tracks both variances and the co-moment in a single pass,
+/// and returns the correlation coefficient in `[-1, 1]`.
+///
+/// Returns NaN when either variable has zero variance,
+/// which includes the cases of zero or one sample.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct Correlation {
+    n: usize,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    co_moment: f64,
+}
+
+#[cfg(feature = "std")]
+impl<I> Lup<I, (f64, f64)> for Correlation {
+    type Inner = f64;
+    fn start() -> Correlation {Correlation::default()}
+    fn it(&mut self, _ind: I, (x, y): (f64, f64)) -> bool {
+        self.n += 1;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / self.n as f64;
+        self.mean_y += dy / self.n as f64;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.co_moment += dx * (y - self.mean_y);
+        true
+    }
+    fn unwrap(self) -> f64 {
+        if self.m2_x == 0.0 || self.m2_y == 0.0 {
+            return f64::NAN;
+        }
+        let r = self.co_moment / (self.m2_x * self.m2_y).sqrt();
+        r.clamp(-1.0, 1.0)
+    }
+}
+
+/// Root mean square loop.
+///
+/// Accumulates the sum of squares and the number of values,
+/// taking the square root of the mean when the loop ends.
+/// For `[f32; N]` and `[f64; N]`, with `N` up to 4, the RMS is computed per component.
+///
+/// Returns NaN when there are no values.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct Rms<T>(pub T, pub usize);
+
+#[cfg(feature = "std")]
+macro_rules! rms_impl {
+    ($f32:ty) => {
+        impl<I> Lup<I, $f32> for Rms<$f32> {
+            type Inner = $f32;
+            fn start() -> Self {Rms(0.0, 0)}
+            fn it(&mut self, _ind: I, val: $f32) -> bool {
+                self.0 += val * val;
+                self.1 += 1;
+                true
+            }
+            fn unwrap(self) -> $f32 {(self.0 / self.1 as $f32).sqrt()}
+        }
+
+        rms_impl!{$f32, 2, 3, 4}
+    };
+    ($f32:ty, $($n:tt),*) => {$(
+        impl<I> Lup<I, [$f32; $n]> for Rms<[$f32; $n]> {
+            type Inner = [$f32; $n];
+            fn start() -> Self {Rms([0.0; $n], 0)}
+            fn it(&mut self, _ind: I, val: [$f32; $n]) -> bool {
+                for (acc, &x) in self.0.iter_mut().zip(val.iter()) {*acc += x * x}
+                self.1 += 1;
+                true
+            }
+            fn unwrap(mut self) -> [$f32; $n] {
+                for acc in self.0.iter_mut() {*acc = (*acc / self.1 as $f32).sqrt()}
+                self.0
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "std")]
+rms_impl!{f32}
+
+#[cfg(feature = "std")]
+rms_impl!{f64}
+
+/// Shannon entropy loop over probabilities.
+///
+/// The body returns probabilities,
+/// and the result is the entropy `-sum(p * ln(p))` in nats.
+/// Probabilities that are zero contribute zero.
+/// Use `EntropyBits` for the entropy in bits.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct Entropy(pub f64);
+
+#[cfg(feature = "std")]
+impl<I> Lup<I, f64> for Entropy {
+    type Inner = f64;
+    fn start() -> Entropy {Entropy(0.0)}
+    fn it(&mut self, _ind: I, p: f64) -> bool {
+        if p != 0.0 {self.0 -= p * p.ln()}
+        true
+    }
+    fn unwrap(self) -> f64 {self.0}
+}
+
+/// Shannon entropy loop over probabilities, in bits.
+///
+/// Same as `Entropy`, but the result is in bits instead of nats.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct EntropyBits(pub Entropy);
+
+#[cfg(feature = "std")]
+impl<I> Lup<I, f64> for EntropyBits {
+    type Inner = f64;
+    fn start() -> EntropyBits {EntropyBits(Entropy(0.0))}
+    fn it(&mut self, ind: I, p: f64) -> bool {self.0.it(ind, p)}
+    fn unwrap(self) -> f64 {(self.0).0 / core::f64::consts::LN_2}
+}
+
+/// Summary statistics loop.
+///
+/// Computes the count, mean, population variance, minimum and maximum in a single pass.
+/// The evidence of the minimum and maximum is the index of the first occurrence.
+///
+/// When there are no samples, the numeric fields are NaN and there is no evidence.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    /// The number of samples.
+    pub count: usize,
+    /// The mean.
+    pub mean: f64,
+    /// The population variance.
+    pub variance: f64,
+    /// The minimum value.
+    pub min: Secret<usize, f64>,
+    /// The maximum value.
+    pub max: Secret<usize, f64>,
+    m2: f64,
+}
+
+impl Lup<usize, f64> for Summary {
+    type Inner = Summary;
+    fn start() -> Summary {
+        Summary {
+            count: 0,
+            mean: 0.0,
+            variance: 0.0,
+            min: Secret {evidence: None, value: f64::NAN},
+            max: Secret {evidence: None, value: f64::NAN},
+            m2: 0.0,
+        }
+    }
+    fn it(&mut self, ind: usize, val: f64) -> bool {
+        self.count += 1;
+        let d = val - self.mean;
+        self.mean += d / self.count as f64;
+        self.m2 += d * (val - self.mean);
+        if self.min.value.is_nan() || val < self.min.value {
+            self.min = Secret {evidence: Some(ind), value: val};
+        }
+        if self.max.value.is_nan() || val > self.max.value {
+            self.max = Secret {evidence: Some(ind), value: val};
+        }
+        true
+    }
+    fn unwrap(mut self) -> Summary {
+        if self.count == 0 {
+            self.mean = f64::NAN;
+            self.variance = f64::NAN;
+        } else {
+            self.variance = self.m2 / self.count as f64;
+        }
+        self
+    }
+}
+
+/// Percentile loop.
+///
+/// Buffers the values and returns the `q`-th quantile on unwrap,
+/// interpolating linearly between the closest ranks.
+/// With `q = 0` and `q = 1`, this is the minimum and maximum.
+///
+/// The loop is constructed with `q` using the constructor syntax of `lup!`,
+/// e.g. `lup!(Percentile(0.9): i by xs => {xs[i]})`.
+/// When started without a constructor, it computes the median.
+///
+/// NaN values are skipped.
+/// Returns NaN when there are no values.
+///
+/// Requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct Percentile(pub f64, pub Vec<f64>);
+
+#[cfg(feature = "alloc")]
+impl Percentile {
+    /// Creates a new loop computing the `q`-th quantile.
+    ///
+    /// Panics if `q` is not in `[0, 1]`.
+    pub fn new(q: f64) -> Percentile {
+        assert!((0.0..=1.0).contains(&q), "Percentile: q must be in [0, 1], got {}", q);
+        Percentile(q, Vec::new())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<I> Lup<I, f64> for Percentile {
+    type Inner = f64;
+    fn start() -> Percentile {Percentile::new(0.5)}
+    fn it(&mut self, _ind: I, val: f64) -> bool {
+        if !val.is_nan() {self.1.push(val)}
+        true
+    }
+    fn unwrap(mut self) -> f64 {
+        if self.1.is_empty() {return f64::NAN}
+        self.1.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let pos = self.0 * (self.1.len() - 1) as f64;
+        let lo = pos as usize;
+        if lo + 1 >= self.1.len() {return self.1[lo]}
+        let t = pos - lo as f64;
+        self.1[lo] + t * (self.1[lo + 1] - self.1[lo])
+    }
+}
+
+/// Streaming quantile loop, using the P² algorithm.
+///
+/// Estimates the `q`-th quantile without storing the values,
+/// by tracking five markers whose heights are adjusted with piecewise-parabolic
+/// interpolation (Jain & Chlamtac, 1985).
+/// Unlike `Percentile`, memory use is constant, which makes it suitable for very long loops.
+///
+/// The result is an approximation.
+/// For smooth distributions and many values, the error is usually
+/// well under one percent of the data range,
+/// but it can be larger for skewed or multimodal data, extreme quantiles
+/// or values arriving in sorted order.
+/// With five values or fewer, the exact quantile is returned,
+/// interpolating like `Percentile`.
+///
+/// The loop is constructed with `q` using the constructor syntax of `lup!`,
+/// e.g. `lup!(P2Quantile(0.9): i by xs => {xs[i]})`.
+/// When started without a constructor, it estimates the median.
+///
+/// NaN values are skipped.
+/// Returns NaN when there are no values.
+#[derive(Debug, Clone)]
+pub struct P2Quantile {
+    /// The quantile to estimate.
+    pub q: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    /// Creates a new loop estimating the `q`-th quantile.
+    ///
+    /// Panics if `q` is not in `[0, 1]`.
+    pub fn new(q: f64) -> P2Quantile {
+        assert!((0.0..=1.0).contains(&q), "P2Quantile: q must be in [0, 1], got {}", q);
+        P2Quantile {
+            q,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * q, 1.0 + 4.0 * q, 3.0 + 2.0 * q, 5.0],
+            increments: [0.0, q / 2.0, q, (1.0 + q) / 2.0, 1.0],
+        }
+    }
+
+    fn adjust(&mut self, i: usize) {
+        let (h, n) = (&mut self.heights, &self.positions);
+        let d = self.desired[i] - n[i];
+        if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+            let d = d.signum();
+            let parabolic = h[i] + d / (n[i + 1] - n[i - 1]) * (
+                (n[i] - n[i - 1] + d) * (h[i + 1] - h[i]) / (n[i + 1] - n[i]) +
+                (n[i + 1] - n[i] - d) * (h[i] - h[i - 1]) / (n[i] - n[i - 1])
+            );
+            h[i] = if h[i - 1] < parabolic && parabolic < h[i + 1] {
+                parabolic
+            } else {
+                let j = if d > 0.0 {i + 1} else {i - 1};
+                h[i] + d * (h[j] - h[i]) / (n[j] - n[i])
+            };
+            self.positions[i] += d;
+        }
+    }
+}
+
+impl<I> Lup<I, f64> for P2Quantile {
+    type Inner = f64;
+    fn start() -> P2Quantile {P2Quantile::new(0.5)}
+    fn it(&mut self, _ind: I, val: f64) -> bool {
+        if val.is_nan() {return true}
+        if self.count < 5 {
+            self.heights[self.count] = val;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            }
+            return true
+        }
+        self.count += 1;
+        let h = &mut self.heights;
+        let k = if val < h[0] {
+            h[0] = val;
+            0
+        } else if val >= h[4] {
+            h[4] = val;
+            3
+        } else {
+            (0..4).find(|&k| val < h[k + 1]).unwrap()
+        };
+        for n in &mut self.positions[k + 1..] {*n += 1.0}
+        for (desired, inc) in self.desired.iter_mut().zip(&self.increments) {*desired += inc}
+        for i in 1..4 {self.adjust(i)}
+        true
+    }
+    fn unwrap(self) -> f64 {
+        if self.count > 5 {return self.heights[2]}
+        if self.count == 0 {return f64::NAN}
+        let mut xs = self.heights;
+        let xs = &mut xs[..self.count];
+        xs.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        let pos = self.q * (xs.len() - 1) as f64;
+        let lo = pos as usize;
+        if lo + 1 >= xs.len() {return xs[lo]}
+        let t = pos - lo as f64;
+        xs[lo] + t * (xs[lo + 1] - xs[lo])
+    }
+}
+
+impl<I> LupWith<f64, I, f64> for P2Quantile {
+    fn start_with(q: f64) -> P2Quantile {P2Quantile::new(q)}
+}
+
+/// Minimum gap loop over consecutive values.
+///
+/// Tracks the smallest absolute difference between each value and the previous one.
+/// The evidence is the index of the second value of the closest pair.
+///
+/// NaN values are skipped, so the values on each side of them form a pair.
+/// Returns NaN without evidence when there are fewer than two values.
+#[derive(Debug, Clone)]
+pub struct MinGap(pub Secret<usize, f64>, pub Option<f64>);
+
+impl Default for MinGap {
+    fn default() -> MinGap {MinGap(Secret {evidence: None, value: f64::NAN}, None)}
+}
+
+impl Lup<usize, f64> for MinGap {
+    type Inner = Secret<usize, f64>;
+    fn start() -> MinGap {MinGap::default()}
+    fn it(&mut self, ind: usize, val: f64) -> bool {
+        if val.is_nan() {return true}
+        if let Some(prev) = self.1 {
+            let gap = if val > prev {val - prev} else {prev - val};
+            if self.0.evidence.is_none() || gap < self.0.value {
+                self.0 = Secret {evidence: Some(ind), value: gap};
+            }
+        }
+        self.1 = Some(val);
+        true
+    }
+    fn unwrap(self) -> Secret<usize, f64> {self.0}
+}
+
+/// Spread loop, the difference between the maximum and the minimum.
+///
+/// Tracks both in one pass.
+/// The evidence is `(index of minimum, index of maximum)`.
+///
+/// Returns NaN without evidence when there are no values,
+/// and 0 when there is one value.
+/// NaN values are skipped, like for `Min` and `Max`.
+#[derive(Debug, Clone)]
+pub struct Spread<I, T>(pub Min<I, T>, pub Max<I, T>);
+
+macro_rules! spread_impl {
+    ($($f32:ty),*) => {$(
+        impl<K: Clone> Lup<K, $f32> for Spread<K, $f32> {
+            type Inner = Secret<(K, K), $f32>;
+            fn start() -> Self {Spread(Min::start(), Max::start())}
+            fn it(&mut self, ind: K, val: $f32) -> bool {
+                self.0.it(ind.clone(), val);
+                self.1.it(ind, val)
+            }
+            fn unwrap(self) -> Secret<(K, K), $f32> {
+                let (min, max) = (self.0.unwrap(), self.1.unwrap());
+                Secret {
+                    evidence: min.evidence.zip(max.evidence),
+                    value: max.value - min.value,
+                }
+            }
+        }
+    )*}
+}
+
+spread_impl!{f32, f64}
+
+#[cfg(feature = "alloc")]
+impl<I> LupWith<f64, I, f64> for Percentile {
+    fn start_with(q: f64) -> Percentile {Percentile::new(q)}
+}
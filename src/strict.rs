@@ -0,0 +1,86 @@
+use *;
+
+/// Error of strict loops, with the index of the first NaN value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NanAt<I>(pub I);
+
+/// Maximum loop that rejects NaN values.
+///
+/// The first NaN value (or invalid value for other `Scalar` types)
+/// stops the loop and returns an error with its index.
+#[derive(Debug, Clone)]
+pub struct MaxStrict<I, T>(pub Result<Secret<I, T>, NanAt<I>>);
+
+/// Minimum loop that rejects NaN values.
+///
+/// The first NaN value (or invalid value for other `Scalar` types)
+/// stops the loop and returns an error with its index.
+#[derive(Debug, Clone)]
+pub struct MinStrict<I, T>(pub Result<Secret<I, T>, NanAt<I>>);
+
+macro_rules! strict_impl {
+    ($name:ident, $op:tt) => {
+        impl<K, S: Scalar> Lup<K, S> for $name<K, S> {
+            type Inner = Result<Secret<K, S>, NanAt<K>>;
+            fn start() -> Self {$name(Ok(Secret {evidence: None, value: S::invalid()}))}
+            fn it(&mut self, ind: K, val: S) -> bool {
+                if val.is_invalid() {
+                    self.0 = Err(NanAt(ind));
+                    return false;
+                }
+                if let Ok(ref mut best) = self.0 {
+                    if best.value.is_invalid() || val $op best.value {
+                        *best = Secret {evidence: Some(ind), value: val};
+                    }
+                }
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+
+        strict_impl!{$name, $op, usize}
+
+        strict_impl!{$name, $op, u32}
+
+        strict_impl!{$name, $op, u16}
+    };
+    ($name:ident, $op:tt, $usize:ty) => {
+        strict_impl!{$name, $op, $usize, [$usize] [($usize, $usize)] [a] (ind, a)}
+
+        strict_impl!{$name, $op, $usize,
+            [($usize, $usize)] [($usize, $usize, $usize)] [(a, b)] (ind, a, b)}
+
+        strict_impl!{$name, $op, $usize,
+            [($usize, $usize, $usize)] [($usize, $usize, $usize, $usize)] [(a, b, c)] (ind, a, b, c)}
+    };
+    ($name:ident, $op:tt, $usize:ty, [$inner:ty] [$outer_ty:ty] [$pat:pat] ($ind:ident, $($e:ident),+)) => {
+        impl<S: Scalar> Lup<$usize, Result<Secret<$inner, S>, NanAt<$inner>>> for $name<$outer_ty, S> {
+            type Inner = Result<Secret<$outer_ty, S>, NanAt<$outer_ty>>;
+            fn start() -> Self {$name(Ok(Secret {evidence: None, value: S::invalid()}))}
+            fn it(&mut self, $ind: $usize, val: Result<Secret<$inner, S>, NanAt<$inner>>) -> bool {
+                match val {
+                    Err(NanAt($pat)) => {
+                        self.0 = Err(NanAt(($ind, $($e),+)));
+                        false
+                    }
+                    Ok(val) => {
+                        if let Ok(ref mut best) = self.0 {
+                            if best.value.is_invalid() || val.value $op best.value {
+                                *best = Secret {
+                                    evidence: val.evidence.map(|$pat| ($ind, $($e),+)),
+                                    value: val.value
+                                };
+                            }
+                        }
+                        true
+                    }
+                }
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    };
+}
+
+strict_impl!{MaxStrict, >}
+
+strict_impl!{MinStrict, <}
@@ -1,54 +1,266 @@
 use *;
+#[cfg(feature = "bigint")]
+use num_bigint::{BigInt, BigUint};
+#[cfg(feature = "nalgebra")]
+use nalgebra::SVector;
+#[cfg(feature = "half")]
+use half::f16;
+#[cfg(feature = "vecmath")]
+use vecmath::{Matrix4, mat4_add};
+#[cfg(feature = "glam")]
+use glam::{Vec2, Vec3, Vec4, DVec2, DVec3, DVec4};
 
 /// Sum loop.
+#[derive(Debug, Clone, Default)]
 pub struct Sum<T>(pub T);
 
-macro_rules! sum_impl {
-    ($usize:ty , $f32:ty) => {
-        impl Lup<$usize, $f32> for Sum<$f32> {
-            type Inner = $f32;
-            fn start() -> Sum<$f32> {Sum(0.0)}
-            fn it(&mut self, _ind: $usize, val: $f32) -> bool {self.0 += val; true}
-            fn unwrap(self) -> $f32 {self.0}
-        }
-
-        impl Lup<$usize, [$f32; 2]> for Sum<[$f32; 2]> {
-            type Inner = [$f32; 2];
-            fn start() -> Sum<[$f32; 2]> {Sum([0.0; 2])}
-            fn it(&mut self, _ind: $usize, val: [$f32; 2]) -> bool {
-                self.0 = [self.0[0] + val[0], self.0[1] + val[1]];
+impl<I, S: Scalar> Lup<I, S> for Sum<S> {
+    type Inner = S;
+    fn start() -> Sum<S> {Sum(S::zero())}
+    fn it(&mut self, _ind: I, val: S) -> bool {self.0 = S::add(self.0, val); true}
+    fn unwrap(self) -> S {self.0}
+}
+
+/// Starts the sum at a seed value, e.g. `lup!(Sum<f64> with 10.0: ...)`.
+impl<I, T> LupWith<T, I, T> for Sum<T> where Sum<T>: Lup<I, T> {
+    fn start_with(seed: T) -> Sum<T> {Sum(seed)}
+}
+
+impl<I, S: Scalar> Lup<I, [S; 2]> for Sum<[S; 2]> {
+    type Inner = [S; 2];
+    fn start() -> Sum<[S; 2]> {Sum([S::zero(); 2])}
+    fn it(&mut self, _ind: I, val: [S; 2]) -> bool {
+        self.0 = [S::add(self.0[0], val[0]), S::add(self.0[1], val[1])];
+        true
+    }
+    fn unwrap(self) -> [S; 2] {self.0}
+}
+
+impl<I, S: Scalar> Lup<I, [S; 3]> for Sum<[S; 3]> {
+    type Inner = [S; 3];
+    fn start() -> Sum<[S; 3]> {Sum([S::zero(); 3])}
+    fn it(&mut self, _ind: I, val: [S; 3]) -> bool {
+        self.0 = [
+            S::add(self.0[0], val[0]),
+            S::add(self.0[1], val[1]),
+            S::add(self.0[2], val[2]),
+        ];
+        true
+    }
+    fn unwrap(self) -> [S; 3] {self.0}
+}
+
+impl<I, S: Scalar> Lup<I, [S; 4]> for Sum<[S; 4]> {
+    type Inner = [S; 4];
+    fn start() -> Sum<[S; 4]> {Sum([S::zero(); 4])}
+    fn it(&mut self, _ind: I, val: [S; 4]) -> bool {
+        self.0 = [
+            S::add(self.0[0], val[0]),
+            S::add(self.0[1], val[1]),
+            S::add(self.0[2], val[2]),
+            S::add(self.0[3], val[3]),
+        ];
+        true
+    }
+    fn unwrap(self) -> [S; 4] {self.0}
+}
+
+/// Sum loop that accumulates `f32` values in `f64`.
+///
+/// The body returns `f32` or `[f32; N]` for `N` up to 4,
+/// and the result is `f64` or `[f64; N]`.
+/// This avoids the drift of summing many `f32` values
+/// without casting every value in the body.
+#[derive(Debug, Clone, Default)]
+pub struct SumWide<T>(pub T);
+
+impl<I> Lup<I, f32> for SumWide<f64> {
+    type Inner = f64;
+    fn start() -> SumWide<f64> {SumWide(0.0)}
+    fn it(&mut self, _ind: I, val: f32) -> bool {self.0 += val as f64; true}
+    fn unwrap(self) -> f64 {self.0}
+}
+
+macro_rules! sum_wide_impl {
+    ($($n:tt),*) => {$(
+        impl<I> Lup<I, [f32; $n]> for SumWide<[f64; $n]> {
+            type Inner = [f64; $n];
+            fn start() -> SumWide<[f64; $n]> {SumWide([0.0; $n])}
+            fn it(&mut self, _ind: I, val: [f32; $n]) -> bool {
+                for (acc, &x) in self.0.iter_mut().zip(val.iter()) {*acc += x as f64}
                 true
             }
-            fn unwrap(self) -> [$f32; 2] {self.0}
+            fn unwrap(self) -> [f64; $n] {self.0}
+        }
+    )*}
+}
+
+sum_wide_impl!{2, 3, 4}
+
+macro_rules! sum_int_impl {
+    ($($int:ty),*) => {$(
+        impl<I> Lup<I, $int> for Sum<$int> {
+            type Inner = $int;
+            fn start() -> Sum<$int> {Sum(0)}
+            fn it(&mut self, _ind: I, val: $int) -> bool {self.0 += val; true}
+            fn unwrap(self) -> $int {self.0}
+        }
+    )*}
+}
+
+sum_int_impl!{u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize}
+
+/// Sum loop where the order of additions does not matter.
+///
+/// Implements `UnorderedAccum`, so loops over a plain range
+/// are split into partial sums that are added at the end.
+/// For floats, this is faster than `Sum`,
+/// but the result might differ slightly from the sequential sum.
+#[derive(Debug, Clone, Default)]
+pub struct SumUnordered<T>(pub T);
+
+impl<I, S: Scalar> Lup<I, S> for SumUnordered<S> {
+    type Inner = S;
+    fn start() -> SumUnordered<S> {SumUnordered(S::zero())}
+    fn it(&mut self, _ind: I, val: S) -> bool {self.0 = S::add(self.0, val); true}
+    fn unwrap(self) -> S {self.0}
+}
+
+/// Sum loop over optional values.
+///
+/// Sums the `Some` values and counts how many were missing,
+/// like aggregating a column with nulls.
+#[derive(Debug, Clone)]
+pub struct SumOpt<T>(pub Tally<T>);
+
+/// The result of a `SumOpt` loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tally<T> {
+    /// The sum of the present values.
+    pub sum: T,
+    /// The number of missing values.
+    pub missing: usize,
+    /// The number of present values.
+    pub present: usize,
+}
+
+impl<I, S: Scalar> Lup<I, Option<S>> for SumOpt<S> {
+    type Inner = Tally<S>;
+    fn start() -> SumOpt<S> {SumOpt(Tally {sum: S::zero(), missing: 0, present: 0})}
+    fn it(&mut self, _ind: I, val: Option<S>) -> bool {
+        match val {
+            Some(val) => {
+                self.0.sum = S::add(self.0.sum, val);
+                self.0.present += 1;
+            }
+            None => self.0.missing += 1,
+        }
+        true
+    }
+    fn unwrap(self) -> Tally<S> {self.0}
+}
+
+macro_rules! sum_impl {
+    ($f32:ty) => {
+        #[cfg(feature = "nalgebra")]
+        impl<I, const N: usize> Lup<I, SVector<$f32, N>> for Sum<SVector<$f32, N>> {
+            type Inner = SVector<$f32, N>;
+            fn start() -> Self {Sum(SVector::zeros())}
+            fn it(&mut self, _ind: I, val: SVector<$f32, N>) -> bool {self.0 += val; true}
+            fn unwrap(self) -> SVector<$f32, N> {self.0}
         }
 
-        impl Lup<$usize, [$f32; 3]> for Sum<[$f32; 3]> {
-            type Inner = [$f32; 3];
-            fn start() -> Sum<[$f32; 3]> {Sum([0.0; 3])}
-            fn it(&mut self, _ind: $usize, val: [$f32; 3]) -> bool {
-                self.0 = [self.0[0] + val[0], self.0[1] + val[1], self.0[2] + val[2]];
+        #[cfg(feature = "vecmath")]
+        impl<I> Lup<I, Matrix4<$f32>> for Sum<Matrix4<$f32>> {
+            type Inner = Matrix4<$f32>;
+            fn start() -> Self {Sum([[0.0; 4]; 4])}
+            fn it(&mut self, _ind: I, val: Matrix4<$f32>) -> bool {
+                self.0 = mat4_add(self.0, val);
                 true
             }
-            fn unwrap(self) -> [$f32; 3] {self.0}
-        }
-
-        impl Lup<$usize, [$f32; 4]> for Sum<[$f32; 4]> {
-            type Inner = [$f32; 4];
-            fn start() -> Sum<[$f32; 4]> {Sum([0.0; 4])}
-            fn it(&mut self, _ind: $usize, val: [$f32; 4]) -> bool {
-                self.0 = [
-                    self.0[0] + val[0],
-                    self.0[1] + val[1],
-                    self.0[2] + val[2],
-                    self.0[3] + val[3],
-                ];
+            fn unwrap(self) -> Matrix4<$f32> {self.0}
+        }
+    }
+}
+
+sum_impl!{f32}
+
+sum_impl!{f64}
+
+#[cfg(feature = "mint")]
+macro_rules! sum_mint_impl {
+    ($($vec:ident : $n:tt),*) => {$(
+        impl<I, S: Scalar> Lup<I, mint::$vec<S>> for Sum<mint::$vec<S>> {
+            type Inner = mint::$vec<S>;
+            fn start() -> Self {Sum([S::zero(); $n].into())}
+            fn it(&mut self, ind: I, val: mint::$vec<S>) -> bool {
+                let mut acc = Sum::<[S; $n]>(self.0.into());
+                acc.it(ind, val.into());
+                self.0 = acc.0.into();
                 true
             }
-            fn unwrap(self) -> [$f32; 4] {self.0}
+            fn unwrap(self) -> mint::$vec<S> {self.0}
+        }
+    )*}
+}
+
+#[cfg(feature = "mint")]
+sum_mint_impl!{Vector2: 2, Vector3: 3, Vector4: 4}
+
+#[cfg(feature = "glam")]
+macro_rules! sum_glam_impl {
+    ($($vec:ident),*) => {$(
+        impl<I> Lup<I, $vec> for Sum<$vec> {
+            type Inner = $vec;
+            fn start() -> Self {Sum($vec::ZERO)}
+            fn it(&mut self, _ind: I, val: $vec) -> bool {self.0 += val; true}
+            fn unwrap(self) -> $vec {self.0}
+        }
+    )*}
+}
+
+#[cfg(feature = "glam")]
+sum_glam_impl!{Vec2, Vec3, Vec4, DVec2, DVec3, DVec4}
+
+/// Sum loop for `f16` values.
+///
+/// The values are accumulated in `f32` to avoid losing precision,
+/// and the result is rounded back to `f16` when the loop ends.
+#[cfg(feature = "half")]
+#[derive(Debug, Clone, Default)]
+pub struct HalfSum(pub f32);
+
+#[cfg(feature = "half")]
+impl<I> Lup<I, f16> for HalfSum {
+    type Inner = f16;
+    fn start() -> Self {HalfSum(0.0)}
+    fn it(&mut self, _ind: I, val: f16) -> bool {self.0 += val.to_f32(); true}
+    fn unwrap(self) -> f16 {f16::from_f32(self.0)}
+}
+
+#[cfg(feature = "bigint")]
+macro_rules! sum_bigint_impl {
+    ($big:ident : $($t:ty),*) => {
+        impl<I> Lup<I, $big> for Sum<$big> {
+            type Inner = $big;
+            fn start() -> Self {Sum($big::from(0u8))}
+            fn it(&mut self, _ind: I, val: $big) -> bool {self.0 += val; true}
+            fn unwrap(self) -> $big {self.0}
         }
+
+        $(
+            impl<I> Lup<I, $t> for Sum<$big> {
+                type Inner = $big;
+                fn start() -> Self {Sum($big::from(0u8))}
+                fn it(&mut self, _ind: I, val: $t) -> bool {self.0 += val; true}
+                fn unwrap(self) -> $big {self.0}
+            }
+        )*
     }
 }
 
-sum_impl!{usize, f32}
+#[cfg(feature = "bigint")]
+sum_bigint_impl!{BigUint: u8, u16, u32, u64, u128, usize}
 
-sum_impl!{usize, f64}
+#[cfg(feature = "bigint")]
+sum_bigint_impl!{BigInt: i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize}
@@ -0,0 +1,68 @@
+use core::cmp;
+use core::ops::Range;
+
+/// Iterates over a 2D range tile by tile.
+///
+/// Every `(i, j)` in `rows` × `cols` is visited once.
+/// The tiles are visited in row-major order,
+/// and the indices within each tile are visited in row-major order.
+///
+/// This is used by the `tiles` syntax of the `lup!` macro:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Sum;
+///
+/// fn main() {
+///     let (h, w) = (3, 5);
+///     let img: Vec<f64> = (0..h * w).map(|x| x as f64).collect();
+///     let sum = lup!(Sum<f64>: i in 0..h, j in 0..w tiles (2, 2) => {img[i * w + j]});
+///     println!("{}", sum); // Prints `105`.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tiles {
+    rows: Range<usize>,
+    cols: Range<usize>,
+    size: (usize, usize),
+    tile: (usize, usize),
+    pos: (usize, usize),
+}
+
+impl Tiles {
+    /// Creates a new tiled iterator with tiles of size `(height, width)`.
+    ///
+    /// Panics if the height or the width of the tiles is zero.
+    pub fn new(rows: Range<usize>, cols: Range<usize>, size: (usize, usize)) -> Tiles {
+        assert!(size.0 > 0 && size.1 > 0, "lup: tile size must be non-zero, got {:?}", size);
+        let start = (rows.start, cols.start);
+        Tiles {rows, cols, size, tile: start, pos: start}
+    }
+}
+
+impl Iterator for Tiles {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.pos.0 >= self.rows.end || self.cols.start >= self.cols.end {
+            return None;
+        }
+        let res = self.pos;
+        self.pos.1 += 1;
+        if self.pos.1 >= cmp::min(self.tile.1 + self.size.1, self.cols.end) {
+            self.pos.1 = self.tile.1;
+            self.pos.0 += 1;
+            if self.pos.0 >= cmp::min(self.tile.0 + self.size.0, self.rows.end) {
+                self.tile.1 += self.size.1;
+                if self.tile.1 >= self.cols.end {
+                    self.tile.1 = self.cols.start;
+                    self.tile.0 += self.size.0;
+                }
+                self.pos = self.tile;
+            }
+        }
+        Some(res)
+    }
+}
@@ -0,0 +1,39 @@
+use *;
+use std::time::{Duration, Instant};
+
+/// Loop that measures the time spent in an inner loop.
+///
+/// Behaves exactly like the inner loop,
+/// and additionally records the elapsed time and the number of iterations.
+///
+/// Requires the `std` feature.
+#[derive(Debug, Clone)]
+pub struct Timed<L>(pub L, pub Instant, pub usize);
+
+/// The result of a `Timed` loop.
+#[derive(Debug, Clone)]
+pub struct Timing<T> {
+    /// The result of the inner loop.
+    pub inner: T,
+    /// The time from the start of the loop to the end.
+    pub elapsed: Duration,
+    /// The number of iterations.
+    pub iterations: usize,
+}
+
+impl<I, T, L: Lup<I, T>> Lup<I, T> for Timed<L> {
+    type Inner = Timing<L::Inner>;
+    fn start() -> Self {Timed(L::start(), Instant::now(), 0)}
+    fn start_sized(hint: Option<usize>) -> Self {Timed(L::start_sized(hint), Instant::now(), 0)}
+    fn it(&mut self, ind: I, val: T) -> bool {
+        self.2 += 1;
+        self.0.it(ind, val)
+    }
+    fn unwrap(self) -> Self::Inner {
+        let iterations = self.2;
+        self.finish(iterations)
+    }
+    fn finish(self, iterations: usize) -> Self::Inner {
+        Timing {inner: self.0.finish(iterations), elapsed: self.1.elapsed(), iterations: self.2}
+    }
+}
@@ -0,0 +1,40 @@
+use *;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+
+/// Uniqueness loop.
+///
+/// The value is `true` if all values are distinct.
+/// Otherwise, the loop stops at the first repeated value,
+/// and the evidence is `(first index, current index)` of the colliding values.
+/// The evidence does not depend on the hasher,
+/// which can be chosen with the second type parameter.
+///
+/// Requires the `std` feature.
+#[derive(Debug, Clone)]
+pub struct IsUnique<T, H = RandomState>(pub Secret<(usize, usize), bool>, pub HashMap<T, usize, H>);
+
+impl<T: Eq + Hash, H: BuildHasher + Default> Default for IsUnique<T, H> {
+    fn default() -> IsUnique<T, H> {
+        IsUnique(Secret {evidence: None, value: true}, HashMap::default())
+    }
+}
+
+impl<T: Eq + Hash, H: BuildHasher + Default> Lup<usize, T> for IsUnique<T, H> {
+    type Inner = Secret<(usize, usize), bool>;
+    fn start() -> Self {IsUnique::default()}
+    fn start_sized(hint: Option<usize>) -> Self {
+        IsUnique(Secret {evidence: None, value: true}, HashMap::with_capacity_and_hasher(hint.unwrap_or(0), H::default()))
+    }
+    fn it(&mut self, ind: usize, val: T) -> bool {
+        if let Some(&first) = self.1.get(&val) {
+            self.0 = Secret {evidence: Some((first, ind)), value: false};
+            false
+        } else {
+            self.1.insert(val, ind);
+            true
+        }
+    }
+    fn unwrap(self) -> Secret<(usize, usize), bool> {self.0}
+}
@@ -0,0 +1,83 @@
+use *;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Implemented by loops where the order of iterations does not matter.
+///
+/// The `lup!` macro splits loops over a plain range, e.g. `i in 0..n` or `i by list`,
+/// into several partial loops that are merged at the end.
+/// This breaks the dependency between iterations, so the compiler can overlap them.
+///
+/// Loops with evidence, such as `Any` or `Max`, must not implement this trait,
+/// because evidence depends on the order of iterations.
+/// Neither do `Sum` and `Prod` of floats, since reordering changes rounding;
+/// use `SumUnordered` to opt in for floats.
+/// `Sum` and `Prod` of integers implement it, since the result is exact.
+pub trait UnorderedAccum<I, T>: Lup<I, T> {
+    /// Merges a partial loop into this one.
+    fn merge(&mut self, other: Self) where Self: Sized;
+}
+
+/// Integer sums and products are exact, so the order does not matter.
+macro_rules! unordered_int_impl {
+    ($($int:ty),*) => {$(
+        impl<I> UnorderedAccum<I, $int> for Sum<$int> {
+            fn merge(&mut self, other: Self) {self.0 += other.0}
+        }
+
+        impl<I> UnorderedAccum<I, $int> for Prod<$int> {
+            fn merge(&mut self, other: Self) {self.0 *= other.0}
+        }
+    )*}
+}
+
+unordered_int_impl!{u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize}
+
+impl<I, S: Scalar> UnorderedAccum<I, S> for SumUnordered<S> {
+    fn merge(&mut self, other: Self) {self.0 = S::add(self.0, other.0)}
+}
+
+impl UnorderedAccum<usize, bool> for BitMask<u64> {
+    fn merge(&mut self, other: Self) {self.0 |= other.0}
+}
+
+impl UnorderedAccum<usize, bool> for BitMask<u128> {
+    fn merge(&mut self, other: Self) {self.0 |= other.0}
+}
+
+#[cfg(feature = "alloc")]
+impl UnorderedAccum<usize, bool> for BitMask<Vec<u64>> {
+    fn merge(&mut self, other: Self) {
+        if other.0.len() > self.0.len() {self.0.resize(other.0.len(), 0)}
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {*a |= *b}
+    }
+}
+
+/// Merges a partial loop into a driver.
+#[doc(hidden)]
+pub type Merger<L, I, T> = fn(&mut Driver<L, I, T>, Driver<L, I, T>);
+
+/// Returns the merge function of a driver when the loop implements `UnorderedAccum`.
+///
+/// Together with `OrderedMerge`, this selects the unrolled expansion of `lup!`
+/// by method resolution, since `(&&driver).lup_merger()` prefers this impl.
+#[doc(hidden)]
+pub trait UnorderedMerge<L, I, T> {
+    fn lup_merger(&self) -> Option<Merger<L, I, T>>;
+}
+
+impl<L: UnorderedAccum<I, T>, I, T> UnorderedMerge<L, I, T> for &Driver<L, I, T> {
+    fn lup_merger(&self) -> Option<Merger<L, I, T>> {
+        Some(Driver::merge)
+    }
+}
+
+/// The fallback of `UnorderedMerge` for loops where the order matters.
+#[doc(hidden)]
+pub trait OrderedMerge<L, I, T> {
+    fn lup_merger(&self) -> Option<Merger<L, I, T>>;
+}
+
+impl<L: Lup<I, T>, I, T> OrderedMerge<L, I, T> for Driver<L, I, T> {
+    fn lup_merger(&self) -> Option<Merger<L, I, T>> {None}
+}
@@ -0,0 +1,42 @@
+use *;
+
+/// Loop that counts iterations until a condition is true.
+///
+/// Stops at the first `true`.
+/// The value is the number of iterations executed, including the stopping one,
+/// and the evidence is the index where the loop stopped.
+/// If the condition is never true, the value is the total number of iterations
+/// and the evidence is `None`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::Until;
+///
+/// fn main() {
+///     let mut x: f64 = 1.0;
+///     let steps = lup!(Until<_>: _i in 0..100 => {
+///         x = 0.5 * (x + 2.0 / x);
+///         (x * x - 2.0).abs() < 1e-12
+///     });
+///     println!("{}", steps.value); // Prints `5`.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Until<I>(pub Secret<I, usize>);
+
+impl<K> Lup<K, bool> for Until<K> {
+    type Inner = Secret<K, usize>;
+    fn start() -> Self {Until(Secret {evidence: None, value: 0})}
+    fn it(&mut self, ind: K, val: bool) -> bool {
+        self.0.value += 1;
+        if val {
+            self.0.evidence = Some(ind);
+            false
+        } else {
+            true
+        }
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
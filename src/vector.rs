@@ -1,4 +1,8 @@
 use *;
+#[cfg(feature = "nalgebra")]
+use nalgebra::{Scalar, SVector};
+#[cfg(feature = "glam")]
+use glam::{Vec2, Vec3, Vec4, DVec2, DVec3, DVec4};
 
 /// Vector construction loop.
 ///
@@ -15,6 +19,7 @@ use *;
 ///     println!("{:?}", a); // Prints `[0.0, 1.0, 2.0, 3.0]`.
 /// }
 /// ```
+#[derive(Debug, Clone, Default)]
 pub struct Vector<T>(pub T);
 
 impl<T: Default + Copy> Lup<usize, T> for Vector<[T; 2]> {
@@ -46,3 +51,100 @@ impl<T: Default + Copy> Lup<usize, T> for Vector<[T; 4]> {
     }
     fn unwrap(self) -> Self::Inner {self.0}
 }
+
+#[cfg(feature = "nalgebra")]
+impl<T: Scalar + Default, const N: usize> Lup<usize, T> for Vector<SVector<T, N>> {
+    type Inner = SVector<T, N>;
+    fn start() -> Self {Vector(SVector::from_element(Default::default()))}
+    fn it(&mut self, ind: usize, val: T) -> bool {
+        self.0[ind] = val;
+        true
+    }
+    fn unwrap(self) -> Self::Inner {self.0}
+}
+
+/// Vector construction loop for ranges that do not start at zero.
+///
+/// The first index of the loop is stored as base offset,
+/// such that the range `2..5` fills the slots `0..3`.
+///
+/// Panics with the index and the slot if the slot is out of bounds.
+///
+/// Example:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lup;
+///
+/// use lup::VectorFrom;
+///
+/// fn main() {
+///     let a = lup!(VectorFrom<[usize; 3]>: i in 2..5 => {i});
+///     println!("{:?}", a); // Prints `[2, 3, 4]`.
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VectorFrom<T>(pub T, pub Option<usize>);
+
+macro_rules! vector_from_impl {
+    ($n:expr) => {
+        impl<T: Default + Copy> Lup<usize, T> for VectorFrom<[T; $n]> {
+            type Inner = [T; $n];
+            fn start() -> Self {VectorFrom([Default::default(); $n], None)}
+            fn it(&mut self, ind: usize, val: T) -> bool {
+                let base = *self.1.get_or_insert(ind);
+                match ind.checked_sub(base) {
+                    Some(slot) if slot < $n => self.0[slot] = val,
+                    _ => panic!("lup: index {} is out of bounds for VectorFrom of length {} \
+                                 starting at index {}", ind, $n, base),
+                }
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    }
+}
+
+vector_from_impl!{2}
+
+vector_from_impl!{3}
+
+vector_from_impl!{4}
+
+#[cfg(feature = "glam")]
+macro_rules! vector_glam_impl {
+    ($($vec:ident : $f32:ty),*) => {$(
+        impl Lup<usize, $f32> for Vector<$vec> {
+            type Inner = $vec;
+            fn start() -> Self {Vector($vec::ZERO)}
+            fn it(&mut self, ind: usize, val: $f32) -> bool {
+                self.0[ind] = val;
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    )*}
+}
+
+#[cfg(feature = "glam")]
+vector_glam_impl!{Vec2: f32, Vec3: f32, Vec4: f32, DVec2: f64, DVec3: f64, DVec4: f64}
+
+#[cfg(feature = "mint")]
+macro_rules! vector_mint_impl {
+    ($($vec:ident : $n:tt),*) => {$(
+        impl<T: Default + Copy> Lup<usize, T> for Vector<mint::$vec<T>> {
+            type Inner = mint::$vec<T>;
+            fn start() -> Self {Vector([Default::default(); $n].into())}
+            fn it(&mut self, ind: usize, val: T) -> bool {
+                let mut components: [T; $n] = self.0.into();
+                components[ind] = val;
+                self.0 = components.into();
+                true
+            }
+            fn unwrap(self) -> Self::Inner {self.0}
+        }
+    )*}
+}
+
+#[cfg(feature = "mint")]
+vector_mint_impl!{Vector2: 2, Vector3: 3, Vector4: 4}